@@ -1,5 +1,15 @@
+use std::path::PathBuf;
+
 use serde::Deserialize;
 
+#[path = "config.rs"]
+#[allow(dead_code)]
+mod config;
+
+#[path = "suslik_jar.rs"]
+#[allow(dead_code)]
+mod suslik_jar;
+
 fn main() {
     if let Err(code) = process(std::env::args().skip(1)) {
         std::process::exit(code);
@@ -20,7 +30,42 @@ where
     // Remove the "russol" argument when `cargo-russol` is invoked as
     // `cargo --cflag russol` (note the space in `cargo russol` rather than a `-`)
     let args: Vec<_> = args.skip_while(|arg| arg == "russol").collect();
+    if args.first().map(String::as_str) == Some("setup") {
+        return setup(&args[1..]);
+    }
+    // Ruslic specific flags are removed, the rest are passed on to `cargo check`
+    let base = config::RuslicConfig {
+        optimistically_allow_private_types: true,
+        subst_result: true,
+        summarise: true,
+        ..Default::default()
+    };
+    let (options, args) = config::RuslicConfig::split_cli(args).map_err(|e| {
+        eprintln!("error: {e}");
+        1
+    })?;
+    let manifest = manifest_path(&args);
+    // Each crate is checked with its own `[package.metadata.russol]` (see `RuslicConfig::for_crate`),
+    // this resolves the config of the root package to validate it and find the output files.
+    let mut base = base;
+    let config = base
+        .apply_env()
+        .and_then(|_| {
+            let mut config = base.clone();
+            config.apply_manifest(&manifest)?;
+            config.apply_options(&options)?;
+            config.validate()?;
+            Ok(config)
+        })
+        .map_err(|e| {
+            eprintln!("error: {e}");
+            1
+        })?;
 
+    // Each crate appends to these
+    for path in config.emit_path.iter().chain(&config.report) {
+        std::fs::write(path, "").expect("could not create output file");
+    }
     let exit_status = std::process::Command::new("cargo")
         .arg("check")
         .args(&args)
@@ -29,30 +74,99 @@ where
         .env("RUST_TOOLCHAIN", get_rust_toolchain_channel())
         .env("RUSTUP_TOOLCHAIN", get_rust_toolchain_channel())
         .env("RUSTC_WRAPPER", russol_rustc_path)
-        .env("RUSLIC_OPTIMISTICALLY_ALLOW_PRIVATE_TYPES", "true")
-        .env("RUSLIC_SUBST_RESULT", "true")
-        .env("RUSLIC_SUMMARISE", "true")
+        .env(config::CONFIG_ENV, base.to_env(&options))
         .status()
         .expect("could not run cargo");
 
-    if !exit_status.success() {
-        return Err(exit_status.code().unwrap_or(-1));
-    }
-
-    let path = args.iter().find(|arg| arg.starts_with("--manifest-path="));
-    // Run fmt after `RUSLIC_SUBST_RESULT`
-    let exit_status = std::process::Command::new("cargo")
-        .arg("fmt")
-        .args(path)
-        .status()
-        .expect("could not run cargo");
-    if !exit_status.success() {
+    if exit_status.success() {
         Ok(())
     } else {
         Err(exit_status.code().unwrap_or(-1))
     }
 }
 
+/// The manifest given with `--manifest-path[=]<path>`, or the one in the current directory.
+fn manifest_path(args: &[String]) -> PathBuf {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(path) = arg.strip_prefix("--manifest-path=") {
+            return PathBuf::from(path);
+        }
+        if arg == "--manifest-path" {
+            if let Some(path) = args.next() {
+                return PathBuf::from(path);
+            }
+        }
+    }
+    PathBuf::from("Cargo.toml")
+}
+
+/// Checks that a suitable `java` is installed and makes sure that there is a SuSLik jar
+/// which `ruslic` can find, building it with `sbt assembly` if needed. The built jar is
+/// copied next to the `ruslic` binary.
+fn setup(args: &[String]) -> Result<(), i32> {
+    match suslik_jar::java_version() {
+        Ok(version) if version >= suslik_jar::MIN_JAVA_VERSION => {
+            println!("Found java version {version}")
+        }
+        Ok(version) => {
+            eprintln!(
+                "error: java version {version} is too old, SuSLik requires at least version {}",
+                suslik_jar::MIN_JAVA_VERSION
+            );
+            return Err(1);
+        }
+        Err(err) => {
+            eprintln!("error: {err}\nhelp: SuSLik requires a Java runtime to be installed");
+            return Err(1);
+        }
+    }
+    if let Ok(suslik) = suslik_jar::SuslikJar::find(None) {
+        println!("Using SuSLik jar at {}", suslik.jar.to_string_lossy());
+        return Ok(());
+    }
+
+    let suslik_dir = match args {
+        [] => std::env::var_os("SUSLIK_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/../suslik"))),
+        [flag, dir] if flag == "--suslik-dir" => PathBuf::from(dir),
+        _ => {
+            eprintln!("usage: cargo russol setup [--suslik-dir <path to SuSLik checkout>]");
+            return Err(1);
+        }
+    };
+    if !suslik_dir.join("build.sbt").is_file() {
+        eprintln!(
+            "error: no SuSLik sources found at {}\nhelp: run `git submodule update --init` or pass `--suslik-dir`",
+            suslik_dir.to_string_lossy()
+        );
+        return Err(1);
+    }
+    println!("Running `sbt assembly` in {}", suslik_dir.to_string_lossy());
+    let mut assembly = std::process::Command::new(if cfg!(windows) { "cmd" } else { "sbt" });
+    if cfg!(windows) {
+        assembly.arg("/c").arg("sbt");
+    }
+    let exit_status = assembly
+        .arg("assembly")
+        .current_dir(&suslik_dir)
+        .status()
+        .expect("`sbt assembly` command failed to start");
+    let built = suslik_jar::assembly_jar(&suslik_dir);
+    if !exit_status.success() || !built.is_file() {
+        eprintln!(
+            "error: running `sbt assembly` failed to create jar file at {}",
+            built.to_string_lossy()
+        );
+        return Err(exit_status.code().unwrap_or(-1));
+    }
+    let installed = suslik_jar::exe_dir().join(suslik_jar::JAR_NAME);
+    std::fs::copy(&built, &installed).expect("failed to copy SuSLik jar");
+    println!("Installed SuSLik jar to {}", installed.to_string_lossy());
+    Ok(())
+}
+
 pub fn get_rust_toolchain_channel() -> String {
     #[derive(Deserialize)]
     struct RustToolchainFile {
@@ -0,0 +1,165 @@
+use rustc_ast::Mutability;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::ty::{
+    self, BorrowKind, BoundRegionKind, ClosureKind, FreeRegion, Region, RegionKind, Ty, TyCtxt,
+    TyKind, UpvarCapture,
+};
+use rustc_span::{def_id::DefId, DUMMY_SP};
+
+use crate::{
+    ruslik_pure::{ExprKind as PExprKind, PureExpression},
+    ruslik_pure_helpers::PureExpressionWalker,
+    ruslik_ssl::Var,
+};
+
+/// A variable captured by a closure, which becomes an extra argument after those of the closure.
+pub(crate) struct Capture<'tcx> {
+    pub var: Var,
+    /// The type of the argument: that of the variable, or a reference to it for a variable
+    /// captured by reference (or by value into a closure which can be called more than once).
+    pub ty: Ty<'tcx>,
+    /// How the solution binds the argument from the variable in the closure body, e.g. `&mut `
+    /// for `let x = &mut x;`. `None` if the variable is the argument as is.
+    rebind: Option<&'static str>,
+}
+
+/// The lifetime of the borrows of the captured variables (the environment of the closure), as
+/// well as of those whose lifetime was inferred.
+fn env_region<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> Region<'tcx> {
+    tcx.mk_region(RegionKind::ReFree(FreeRegion {
+        scope: def_id,
+        bound_region: BoundRegionKind::BrEnv,
+    }))
+}
+
+fn closure_substs<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> ty::ClosureSubsts<'tcx> {
+    match tcx.type_of(def_id).kind() {
+        TyKind::Closure(_, substs) => substs.as_closure(),
+        other => unreachable!("Not a closure: {other:?}"),
+    }
+}
+
+/// Is `def_id` a closure (rather than a generator).
+pub(crate) fn is_closure(tcx: TyCtxt, def_id: DefId) -> bool {
+    tcx.is_closure(def_id) && tcx.type_of(def_id).is_closure()
+}
+
+/// The arguments and return type of the closure `def_id`, the captured variables come last.
+pub(crate) fn closure_sig<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+) -> (Vec<(Var, Ty<'tcx>)>, Ty<'tcx>) {
+    let env = env_region(tcx, def_id);
+    let sig = tcx.liberate_late_bound_regions(def_id, closure_substs(tcx, def_id).sig());
+    let sig = tcx.fold_regions(sig, |r, _| if r.is_erased() { env } else { r });
+    // The arguments of a closure are passed as a tuple
+    let args = tcx
+        .fn_arg_names(def_id)
+        .iter()
+        .map(|id| Var::arg(id.name))
+        .zip(sig.inputs()[0].tuple_fields())
+        .chain(captures(tcx, def_id).into_iter().map(|c| (c.var, c.ty)))
+        .collect();
+    (args, sig.output())
+}
+
+/// The variables captured by the closure `def_id`. Precise captures (e.g. of `x.f`) are taken
+/// as capturing the whole variable, in the strongest of their capture kinds.
+pub(crate) fn captures<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> Vec<Capture<'tcx>> {
+    let env = env_region(tcx, def_id);
+    let kind = closure_substs(tcx, def_id).kind();
+    let param_env = tcx.param_env(def_id);
+    let local = def_id.expect_local();
+    let roots = match tcx.typeck(local).closure_min_captures.get(&local) {
+        Some(roots) => roots,
+        None => return Vec::new(),
+    };
+    roots
+        .iter()
+        .map(|(root, places)| {
+            let var_ty = places[0].place.base_ty;
+            let var_ty = tcx.fold_regions(var_ty, |r, _| if r.is_erased() { env } else { r });
+            let by_value = places
+                .iter()
+                .any(|p| p.info.capture_kind == UpvarCapture::ByValue);
+            let by_mut = places.iter().any(|p| {
+                matches!(
+                    p.info.capture_kind,
+                    UpvarCapture::ByRef(BorrowKind::MutBorrow | BorrowKind::UniqueImmBorrow)
+                )
+            });
+            let is_copy = var_ty.is_copy_modulo_regions(tcx.at(DUMMY_SP), param_env);
+            let (ty, rebind) = if (by_value && kind == ClosureKind::FnOnce) || (is_copy && !by_mut)
+            {
+                (var_ty, None)
+            } else if by_mut || (by_value && kind == ClosureKind::FnMut) {
+                match var_ty.kind() {
+                    // Reborrow rather than borrowing the reference
+                    TyKind::Ref(_, inner, Mutability::Mut) => {
+                        (tcx.mk_mut_ref(env, *inner), Some("&mut *"))
+                    }
+                    _ => (tcx.mk_mut_ref(env, var_ty), Some("&mut ")),
+                }
+            } else {
+                (tcx.mk_imm_ref(env, var_ty), Some("&"))
+            };
+            Capture {
+                var: Var::arg(tcx.hir().name(*root)),
+                ty,
+                rebind,
+            }
+        })
+        .collect()
+}
+
+/// The specs of a closure refer to the captured variables themselves, those which became
+/// references are dereferenced.
+pub(crate) fn deref_captures<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    spec: &mut PureExpression<'tcx>,
+) {
+    let refs = captures(tcx, def_id)
+        .into_iter()
+        .filter(|c| matches!(c.rebind, Some("&") | Some("&mut ")))
+        .map(|c| (c.var, c.ty))
+        .collect();
+    spec.walk_mut(&mut DerefCaptures(refs));
+}
+
+struct DerefCaptures<'tcx>(FxHashMap<Var, Ty<'tcx>>);
+impl<'tcx> PureExpressionWalker<'tcx> for DerefCaptures<'tcx> {
+    fn walk_expr_mut(&mut self, e: &mut PureExpression<'tcx>) {
+        if let PExprKind::Var(var) = e.kind() && let Some(ty) = self.0.get(var) {
+            *e = PExprKind::Var(*var).with_ty(*ty).deref(false);
+            return;
+        }
+        e.walk_mut(self);
+    }
+    fn walk_kind_mut(&mut self, k: &mut PExprKind<'tcx>) {
+        k.walk_mut(self);
+    }
+}
+
+/// The solution for a closure is written in terms of its arguments, so it first binds those
+/// which are references to the captured variables.
+pub(crate) fn rebind_captures(tcx: TyCtxt, def_id: DefId, body: String) -> String {
+    if !is_closure(tcx, def_id) {
+        return body;
+    }
+    let indent: String = body
+        .lines()
+        .nth(1)
+        .unwrap_or_default()
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect();
+    let rebinds: String = captures(tcx, def_id)
+        .into_iter()
+        .filter_map(|c| {
+            let name = c.var.uuid();
+            Some(format!("{indent}let {name} = {}{name};\n", c.rebind?))
+        })
+        .collect();
+    format!("\n{rebinds}{}", body.trim_start_matches('\n'))
+}
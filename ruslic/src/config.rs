@@ -0,0 +1,440 @@
+//! Configuration of `ruslic`. Each option can be set (in order of precedence) with a
+//! `--kebab-case[=value]` command line flag, under `[package.metadata.russol]` in the
+//! `Cargo.toml` of the crate being checked, or with a `RUSLIC_SCREAMING_CASE` environment
+//! variable. This module only depends on `std`, `serde` and `toml`, since it is shared
+//! with the `cargo-russol` binary.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The resolved config is passed from `cargo-russol` to `ruslic` (as `RUSTC_WRAPPER`) in this variable.
+pub const CONFIG_ENV: &str = "RUSLIC_CONFIG";
+
+/// Options given on the command line, as `(option, value)` pairs.
+pub type CliOptions = Vec<(String, String)>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuslicConfig {
+    /// Timeout in ms per function to be synthesized
+    pub timeout: u64,
+    /// How many instances of SuSLik to run in parallel
+    pub thread_count: usize,
+    /// Write the first solution back into the source file
+    pub subst_result: bool,
+    /// Print a summary of all results at the end
+    pub summarise: bool,
+    /// Also print the summary as json (requires `summarise`)
+    pub summarise_json: bool,
+    /// Have SuSLik output a `trace.json` of the search
+    pub output_trace: bool,
+    /// Abort if SuSLik fails to synthesize a function, rather than reporting it as unsolvable
+    pub fail_on_unsynth: bool,
+    /// Use full def paths when naming predicates
+    pub use_full_names: bool,
+    /// Only print solutions with more than this many lines
+    pub print_sln_above: usize,
+    /// Assume that private fields of types from other crates can be accessed
+    pub optimistically_allow_private_types: bool,
+    /// Path to the SuSLik jar to use (see `SuslikJar::find`)
+    pub suslik_jar: Option<PathBuf>,
+    /// Only synthesize fns whose def path matches one of these globs
+    pub only: Vec<String>,
+    /// Never synthesize fns whose def path matches one of these globs
+    pub skip: Vec<String>,
+    /// Only synthesize fns whose body is `ruslik!()`, `todo!()` or `unimplemented!()`
+    pub only_holes: bool,
+    /// Also synthesize (and with `subst-result` replace) fns which already have a body
+    pub synth_bodies: bool,
+    /// What to do with the substituted solutions, see `Emit`
+    pub emit: Emit,
+    /// Append the `diff` or `json` output to this file rather than printing it
+    pub emit_path: Option<PathBuf>,
+    /// Which of multiple solutions (see `--solutions=N` in `#[params]`) to substitute
+    pub solution_strategy: SolutionStrategy,
+    /// Append a json line per synthesized fn to this file (see `report.rs`)
+    pub report: Option<PathBuf>,
+    /// Check that the pre and postcondition are satisfiable before synthesis (see `vacuity.rs`)
+    pub check_vacuity: bool,
+    /// Offer the integer operations with built-in specs (see `int_ops.rs`) as components
+    pub int_components: bool,
+    /// Load the built-in specs of `Option`, `Box`, `std::mem` etc. (see `std_specs.rs`)
+    pub std_specs: bool,
+    /// Drop the built-in specs whose std path matches one of these globs
+    pub skip_std_specs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Emit {
+    /// Overwrite the source files (only with `subst-result`)
+    Write,
+    /// Leave the source files untouched and output a unified diff, which can be `git apply`ed
+    Diff,
+    /// Leave the source files untouched and output one json object per edit, with the file,
+    /// the byte range to replace and the replacement
+    Json,
+}
+impl FromStr for Emit {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "write" => Ok(Self::Write),
+            "diff" => Ok(Self::Diff),
+            "json" => Ok(Self::Json),
+            _ => Err("expected one of `write`, `diff` or `json`".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SolutionStrategy {
+    /// The first one found
+    First,
+    /// The one with the fewest lines of code
+    Smallest,
+    /// The one with the fewest AST nodes
+    FewestNodes,
+    /// The first one with which the crate still type and borrow checks
+    Checked,
+}
+impl FromStr for SolutionStrategy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(Self::First),
+            "smallest" => Ok(Self::Smallest),
+            "fewest-nodes" => Ok(Self::FewestNodes),
+            "checked" => Ok(Self::Checked),
+            _ => {
+                Err("expected one of `first`, `smallest`, `fewest-nodes` or `checked`".to_string())
+            }
+        }
+    }
+}
+
+impl Default for RuslicConfig {
+    fn default() -> Self {
+        Self {
+            timeout: 1_000_000,
+            thread_count: 8,
+            subst_result: false,
+            summarise: false,
+            summarise_json: false,
+            output_trace: false,
+            fail_on_unsynth: true,
+            use_full_names: false,
+            print_sln_above: 0,
+            optimistically_allow_private_types: false,
+            suslik_jar: None,
+            only: Vec::new(),
+            skip: Vec::new(),
+            only_holes: false,
+            synth_bodies: false,
+            emit: Emit::Write,
+            emit_path: None,
+            solution_strategy: SolutionStrategy::First,
+            report: None,
+            check_vacuity: true,
+            int_components: false,
+            std_specs: true,
+            skip_std_specs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigError(pub String);
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Where a config value came from, for error messages.
+#[derive(Debug, Clone, Copy)]
+enum Source<'a> {
+    Cli,
+    Manifest(&'a Path),
+    Env,
+}
+
+fn parse<T: FromStr>(key: &str, value: &str, source: Source) -> Result<T, ConfigError>
+where
+    T::Err: fmt::Display,
+{
+    value.parse().map_err(|e| {
+        let from = match source {
+            Source::Cli => format!("`--{key}`"),
+            Source::Manifest(path) => format!(
+                "`{key}` in `[package.metadata.russol]` of {}",
+                path.to_string_lossy()
+            ),
+            Source::Env => format!("`{}`", env_name(key)),
+        };
+        ConfigError(format!("invalid value \"{value}\" for {from}: {e}"))
+    })
+}
+
+fn env_name(key: &str) -> String {
+    format!("RUSLIC_{}", key.replace('-', "_").to_uppercase())
+}
+
+impl RuslicConfig {
+    pub const KEYS: &'static [&'static str] = &[
+        "timeout",
+        "thread-count",
+        "subst-result",
+        "summarise",
+        "summarise-json",
+        "output-trace",
+        "fail-on-unsynth",
+        "use-full-names",
+        "print-sln-above",
+        "optimistically-allow-private-types",
+        "suslik-jar",
+        "only",
+        "skip",
+        "only-holes",
+        "synth-bodies",
+        "emit",
+        "emit-path",
+        "solution-strategy",
+        "report",
+        "check-vacuity",
+        "int-components",
+        "std-specs",
+        "skip-std-specs",
+    ];
+    fn is_flag(key: &str) -> bool {
+        !matches!(
+            key,
+            "timeout"
+                | "thread-count"
+                | "print-sln-above"
+                | "suslik-jar"
+                | "only"
+                | "skip"
+                | "skip-std-specs"
+                | "emit"
+                | "emit-path"
+                | "solution-strategy"
+                | "report"
+        )
+    }
+
+    fn set(&mut self, key: &str, value: &str, source: Source) -> Result<(), ConfigError> {
+        match key {
+            "timeout" => self.timeout = parse(key, value, source)?,
+            "thread-count" => self.thread_count = parse(key, value, source)?,
+            "subst-result" => self.subst_result = parse(key, value, source)?,
+            "summarise" => self.summarise = parse(key, value, source)?,
+            "summarise-json" => self.summarise_json = parse(key, value, source)?,
+            "output-trace" => self.output_trace = parse(key, value, source)?,
+            "fail-on-unsynth" => self.fail_on_unsynth = parse(key, value, source)?,
+            "use-full-names" => self.use_full_names = parse(key, value, source)?,
+            "print-sln-above" => self.print_sln_above = parse(key, value, source)?,
+            "optimistically-allow-private-types" => {
+                self.optimistically_allow_private_types = parse(key, value, source)?
+            }
+            "suslik-jar" => self.suslik_jar = Some(PathBuf::from(value)),
+            // Can be given multiple times, or as a comma separated list
+            "only" => self.only.extend(value.split(',').map(String::from)),
+            "skip" => self.skip.extend(value.split(',').map(String::from)),
+            "only-holes" => self.only_holes = parse(key, value, source)?,
+            "synth-bodies" => self.synth_bodies = parse(key, value, source)?,
+            "emit" => self.emit = parse(key, value, source)?,
+            "emit-path" => self.emit_path = Some(PathBuf::from(value)),
+            "solution-strategy" => self.solution_strategy = parse(key, value, source)?,
+            "report" => self.report = Some(PathBuf::from(value)),
+            "check-vacuity" => self.check_vacuity = parse(key, value, source)?,
+            "int-components" => self.int_components = parse(key, value, source)?,
+            "std-specs" => self.std_specs = parse(key, value, source)?,
+            "skip-std-specs" => self
+                .skip_std_specs
+                .extend(value.split(',').map(String::from)),
+            _ => return Err(ConfigError(format!("unknown ruslic option `{key}`"))),
+        }
+        Ok(())
+    }
+
+    /// Applies all `RUSLIC_*` environment variables which are set.
+    pub fn apply_env(&mut self) -> Result<(), ConfigError> {
+        for key in Self::KEYS {
+            if let Ok(value) = std::env::var(env_name(key)) {
+                self.set(key, &value, Source::Env)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the `[package.metadata.russol]` table of the given `Cargo.toml`, if there is one.
+    pub fn apply_manifest(&mut self, manifest: &Path) -> Result<(), ConfigError> {
+        let content = match std::fs::read_to_string(manifest) {
+            Ok(content) => content,
+            Err(_) => return Ok(()),
+        };
+        let toml: toml::Value = toml::from_str(&content).map_err(|e| {
+            ConfigError(format!(
+                "failed to parse {}: {e}",
+                manifest.to_string_lossy()
+            ))
+        })?;
+        let table = match toml
+            .get("package")
+            .and_then(|p| p.get("metadata"))
+            .and_then(|m| m.get("russol"))
+        {
+            Some(table) => table,
+            None => return Ok(()),
+        };
+        let table = table.as_table().ok_or_else(|| {
+            ConfigError(format!(
+                "`package.metadata.russol` in {} should be a table",
+                manifest.to_string_lossy()
+            ))
+        })?;
+        for (key, value) in table {
+            let values = match value {
+                toml::Value::Array(values) => values.iter().collect(),
+                value => vec![value],
+            };
+            for value in values {
+                let value = match value {
+                    toml::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                self.set(&key.replace('_', "-"), &value, Source::Manifest(manifest))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes all `--option[=value]` flags for known options, returning them as `(option, value)`
+    /// pairs along with the remaining args. Boolean options may be given without a value to set
+    /// them to `true`.
+    pub fn split_cli(args: Vec<String>) -> Result<(CliOptions, Vec<String>), ConfigError> {
+        let mut options = Vec::new();
+        let mut rest = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let flag = match arg.strip_prefix("--") {
+                Some(flag) => flag,
+                None => {
+                    rest.push(arg);
+                    continue;
+                }
+            };
+            let (key, value) = match flag.split_once('=') {
+                Some((key, value)) => (key, Some(value.to_string())),
+                None => (flag, None),
+            };
+            if !Self::KEYS.contains(&key) {
+                rest.push(arg);
+                continue;
+            }
+            let value = match value {
+                Some(value) => value,
+                None if Self::is_flag(key) => "true".to_string(),
+                None => args
+                    .next()
+                    .ok_or_else(|| ConfigError(format!("missing value for `--{key}`")))?,
+            };
+            options.push((key.to_string(), value));
+        }
+        Ok((options, rest))
+    }
+
+    /// Applies options split off the command line by `split_cli`.
+    pub fn apply_options(&mut self, options: &CliOptions) -> Result<(), ConfigError> {
+        for (key, value) in options {
+            self.set(key, value, Source::Cli)?;
+        }
+        Ok(())
+    }
+
+    /// Applies and removes all `--option[=value]` flags for known options, returning the remaining args.
+    pub fn apply_cli(&mut self, args: Vec<String>) -> Result<Vec<String>, ConfigError> {
+        let (options, rest) = Self::split_cli(args)?;
+        self.apply_options(&options)?;
+        Ok(rest)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.timeout == 0 {
+            return Err(ConfigError("`timeout` must be greater than 0".to_string()));
+        }
+        if self.thread_count == 0 {
+            return Err(ConfigError("`thread-count` must be at least 1".to_string()));
+        }
+        if self.only_holes && self.synth_bodies {
+            return Err(ConfigError(
+                "`only-holes` and `synth-bodies` cannot both be set".to_string(),
+            ));
+        }
+        if self.summarise_json && !self.summarise {
+            return Err(ConfigError(
+                "`summarise-json` requires `summarise` to be set".to_string(),
+            ));
+        }
+        if self.emit_path.is_some() && self.emit == Emit::Write {
+            return Err(ConfigError(
+                "`emit-path` requires `emit` to be `diff` or `json`".to_string(),
+            ));
+        }
+        match &self.suslik_jar {
+            Some(jar) if !jar.is_file() => Err(ConfigError(format!(
+                "`suslik-jar` is set to {}, which is not a file",
+                jar.to_string_lossy()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Layers `base` with the environment, then the manifest (if any) and finally the command line.
+    pub fn load(
+        base: Self,
+        manifest: Option<&Path>,
+        args: Vec<String>,
+    ) -> Result<(Self, Vec<String>), ConfigError> {
+        let mut config = base;
+        config.apply_env()?;
+        if let Some(manifest) = manifest {
+            config.apply_manifest(manifest)?;
+        }
+        let args = config.apply_cli(args)?;
+        config.validate()?;
+        Ok((config, args))
+    }
+
+    /// Whether solutions should be substituted into the source, either on disk or as output.
+    pub fn substitutes(&self) -> bool {
+        self.subst_result || self.emit != Emit::Write
+    }
+
+    /// Passes `self` (the config before any manifest was applied) and the command line options
+    /// on to each crate, which layers its own manifest in between (see `for_crate`).
+    pub fn to_env(&self, options: &CliOptions) -> String {
+        serde_json::to_string(&(self, options)).unwrap()
+    }
+    /// The config passed down by `cargo-russol`, if any, resolved for the crate with the given manifest.
+    pub fn for_crate(manifest: Option<&Path>) -> Option<Result<Self, ConfigError>> {
+        let value = std::env::var(CONFIG_ENV).ok()?;
+        let resolve = || {
+            let (mut config, options): (Self, CliOptions) = serde_json::from_str(&value)
+                .map_err(|e| ConfigError(format!("invalid `{CONFIG_ENV}`: {e}")))?;
+            if let Some(manifest) = manifest {
+                config.apply_manifest(manifest)?;
+            }
+            config.apply_options(&options)?;
+            config.validate()?;
+            Ok(config)
+        };
+        Some(resolve())
+    }
+}
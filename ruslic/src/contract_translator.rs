@@ -3,21 +3,25 @@ use rustc_hir::{
     def_id::{DefId, LocalDefId},
     HirId,
 };
+use rustc_ast::ast::{LitIntType, LitKind};
 use rustc_middle::{
-    mir::Field,
+    mir::{Field, UnOp},
     thir::{
         self, AdtExpr, ArmId, BindingMode, Block, ExprId, ExprKind, Guard, Pat, PatKind, StmtId,
         StmtKind, Thir,
     },
-    ty::{adjustment::PointerCast, SubstsRef, TyCtxt, TyKind, WithOptConstParam},
+    ty::{adjustment::PointerCast, SubstsRef, Ty, TyCtxt, TyKind, TypeAndMut, WithOptConstParam},
 };
+use rustc_span::{sym, Symbol};
 use rustc_target::abi::VariantIdx;
 
 use crate::{
     constant::{translate_constant, try_to_bits},
+    int_ops,
     ruslik_pure::{self, BuiltinCallKind, CallInfo, ExprKind as Expr, PureExpression, UnOpKind},
     ruslik_ssl::Var,
-    ruslik_types::AdtIdent,
+    ruslik_types::{self, AdtIdent},
+    suslik_translate::{guard_ty, is_cell, shared_ptr_ty},
 };
 
 pub type VarMap<'tcx> = FxHashMap<rustc_hir::HirId, ruslik_pure::PureExpression<'tcx>>;
@@ -39,6 +43,43 @@ pub fn to_expr<'tcx>(tcx: TyCtxt<'tcx>, id: LocalDefId) -> (PureExpression<'tcx>
     (thir_term.expr_term(expr), thir_term.ast_nodes)
 }
 
+/// Translate an expression from within the body of `item_id` (e.g. a condition which holds at a sketch hole).
+pub fn thir_expr_to_expr<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    item_id: LocalDefId,
+    thir: &Thir<'tcx>,
+    var_map: VarMap<'tcx>,
+    expr: ExprId,
+) -> PureExpression<'tcx> {
+    let mut thir_term = ThirTerm {
+        tcx,
+        item_id,
+        thir,
+        var_map,
+        ast_nodes: 0,
+    };
+    thir_term.expr_term(expr)
+}
+
+/// The condition under which `pat` matches `root`, and what each binding in `pat` is bound to.
+pub fn pattern_to_expr<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    item_id: LocalDefId,
+    thir: &Thir<'tcx>,
+    pat: &Pat<'tcx>,
+    root: PureExpression<'tcx>,
+) -> (PureExpression<'tcx>, VarMap<'tcx>) {
+    let mut thir_term = ThirTerm {
+        tcx,
+        item_id,
+        thir,
+        var_map: FxHashMap::default(),
+        ast_nodes: 0,
+    };
+    let cond = thir_term.pattern_term(pat, root);
+    (cond, thir_term.var_map)
+}
+
 struct ThirTerm<'a, 'tcx> {
     tcx: TyCtxt<'tcx>,
     item_id: LocalDefId,
@@ -70,7 +111,11 @@ impl<'a, 'tcx> ThirTerm<'a, 'tcx> {
             }
             ExprKind::Binary { op, lhs, rhs } => {
                 self.ast_nodes += 1;
-                Expr::BinOp(op.to_hir_binop(), Box::new(self.expr_term(lhs)), Box::new(self.expr_term(rhs))).with_ty(ty)
+                let (op, lhs, rhs) = (op.to_hir_binop(), self.expr_term(lhs), self.expr_term(rhs));
+                match int_ops::binary(self.tcx, op, lhs.clone(), rhs.clone()) {
+                    Some(expr) => expr,
+                    None => Expr::BinOp(op, Box::new(lhs), Box::new(rhs)).with_ty(ty),
+                }
             }
             ExprKind::LogicalOp { op, lhs, rhs } => {
                 self.ast_nodes += 1;
@@ -82,7 +127,11 @@ impl<'a, 'tcx> ThirTerm<'a, 'tcx> {
             }
             ExprKind::Unary { op, arg } => {
                 self.ast_nodes += 1;
-                Expr::UnOp(UnOpKind::UnOp(op), Box::new(self.expr_term(arg))).with_ty(ty)
+                let arg = self.expr_term(arg);
+                match op {
+                    UnOp::Not if let Some(expr) = int_ops::not(self.tcx, arg.clone()) => expr,
+                    _ => Expr::UnOp(UnOpKind::UnOp(op), Box::new(arg)).with_ty(ty),
+                }
             }
             // Result or local var:
             ExprKind::VarRef { id } => {
@@ -111,6 +160,37 @@ impl<'a, 'tcx> ThirTerm<'a, 'tcx> {
                 self.ast_nodes += 1;
                 let mut arg_exprs: Vec<_> = args.iter().map(|arg| self.expr_term(*arg)).collect();
                 let ci = match self.get_stub_kind(*id, substs) {
+                    None if let Some(expr) = int_ops::inline_call(self.tcx, *id, substs, &arg_exprs) => return expr,
+                    None if let Some(expr) = self.string_eq(*id, substs, &arg_exprs) => return expr,
+                    None if self.tcx.def_path_str(*id) == "std::ops::Deref::deref"
+                        && shared_ptr_ty(self.tcx, substs.type_at(0)).is_some() => {
+                        // Like a shared reference: `*rc` is the `T` behind it
+                        let ptr = arg_exprs.pop().unwrap().deref(false);
+                        let inner_ty = shared_ptr_ty(self.tcx, ptr.ty()).unwrap();
+                        let (f, v) = ruslik_types::OLD;
+                        return Expr::Field(Box::new(ptr), v, f).with_ty(inner_ty).borrow(ty);
+                    }
+                    None if matches!(self.tcx.def_path_str(*id).as_str(), "std::ops::Deref::deref" | "std::ops::DerefMut::deref_mut")
+                        && guard_ty(self.tcx, substs.type_at(0)).is_some() => {
+                        // A `Ref` or `RefMut` guard is the reference it hands out
+                        let mut guard = arg_exprs.pop().unwrap().deref(false);
+                        let (r, inner_ty, mutbl) = guard_ty(self.tcx, guard.ty()).unwrap();
+                        *guard.ty_mut() = self.tcx.mk_ref(r, TypeAndMut { ty: inner_ty, mutbl });
+                        return guard;
+                    }
+                    None if let Some(kind) = self.get_abstract_builtin(*id, substs) => {
+                        let vec = arg_exprs.remove(0).deref(false);
+                        if let (BuiltinCallKind::Len, Some(lit)) = (kind, str_lit(&vec)) {
+                            return Expr::Lit(LitKind::Int(lit.as_str().len() as u128, LitIntType::Unsuffixed)).with_ty(ty);
+                        }
+                        arg_exprs.insert(0, vec);
+                        if let BuiltinCallKind::VecIndex = kind {
+                            // `v[i]` is `*Index::index(&v, i)`, so give back a reference to the element
+                            let elem_ty = ty.builtin_deref(false).unwrap().ty;
+                            return Expr::Call(CallInfo::Builtin(kind), arg_exprs).with_ty(elem_ty).borrow(ty);
+                        }
+                        CallInfo::Builtin(kind)
+                    }
                     None => CallInfo::Pure(*id, substs),
                     Some(RuslikStub::Snap) => {
                         self.ast_nodes -= 1; // Automatically added by `===`
@@ -125,6 +205,33 @@ impl<'a, 'tcx> ThirTerm<'a, 'tcx> {
                         arg_exprs.push(deref);
                         CallInfo::Builtin(BuiltinCallKind::SetConstruct)
                     }
+                    Some(RuslikStub::SeqNew) => {
+                        assert!(args.len() == 1); assert!(ty.to_string().starts_with("russol_contracts::Seq"));
+                        let deref = arg_exprs.pop().unwrap().deref(false);
+                        arg_exprs.push(deref);
+                        CallInfo::Builtin(BuiltinCallKind::SeqConstruct)
+                    }
+                    Some(s@RuslikStub::SeqTake) |
+                    Some(s@RuslikStub::SeqSkip) => {
+                        assert!(args.len() == 2);
+                        if let RuslikStub::SeqTake = s {
+                            CallInfo::Builtin(BuiltinCallKind::SeqTake)
+                        } else {
+                            CallInfo::Builtin(BuiltinCallKind::SeqSkip)
+                        }
+                    }
+                    Some(RuslikStub::Elems) => {
+                        assert!(args.len() == 1);
+                        let vec = arg_exprs.pop().unwrap().deref(false);
+                        arg_exprs.push(vec);
+                        CallInfo::Builtin(BuiltinCallKind::VecElems)
+                    }
+                    Some(RuslikStub::Contents) => {
+                        assert!(args.len() == 1);
+                        let cell = arg_exprs.pop().unwrap().deref(false);
+                        let contents_ty = ty.builtin_deref(false).unwrap().ty;
+                        return Expr::Call(CallInfo::Builtin(BuiltinCallKind::CellGet), vec![cell]).with_ty(contents_ty).borrow(ty);
+                    }
                     Some(RuslikStub::In) => {
                         assert!(args.len() == 2);
                         let elem = arg_exprs.pop().unwrap().deref(false);
@@ -220,7 +327,7 @@ impl<'a, 'tcx> ThirTerm<'a, 'tcx> {
             ExprKind::Cast { source } => {
                 self.ast_nodes += 1;
                 let expr = self.expr_term(source);
-                expr.get_kind().with_ty(ty)
+                int_ops::cast(self.tcx, expr, ty)
             }
             ExprKind::Use { source } => self.expr_term(source),
             ExprKind::NeverToAny { .. } => Expr::Never.with_ty(ty),
@@ -397,6 +504,16 @@ impl<'a, 'tcx> ThirTerm<'a, 'tcx> {
                 Some(RuslikStub::Snap)
             } else if fn_name.starts_with("russol_contracts::Set::") && fn_name.ends_with("::new") {
                 Some(RuslikStub::SetNew)
+            } else if fn_name.starts_with("russol_contracts::Seq::") && fn_name.ends_with("::new") {
+                Some(RuslikStub::SeqNew)
+            } else if fn_name.starts_with("russol_contracts::Seq::") && fn_name.ends_with("::take") {
+                Some(RuslikStub::SeqTake)
+            } else if fn_name.starts_with("russol_contracts::Seq::") && fn_name.ends_with("::skip") {
+                Some(RuslikStub::SeqSkip)
+            } else if fn_name == "russol_contracts::Elems::elems" {
+                Some(RuslikStub::Elems)
+            } else if fn_name == "russol_contracts::Contents::contents" {
+                Some(RuslikStub::Contents)
             } else {
                 todo!("Unsupported builtin fn encountered: {}", fn_name)
             }
@@ -431,6 +548,47 @@ impl<'a, 'tcx> ThirTerm<'a, 'tcx> {
             None
         }
     }
+    /// The fns of the abstract `Vec`, `String`, `str` and `Cell` types which specifications can
+    /// use: `v.len()`, `v[i]` and `c.get()`.
+    fn get_abstract_builtin(&self, id: DefId, substs: SubstsRef<'tcx>) -> Option<BuiltinCallKind> {
+        let is_vec = |ty: Ty<'tcx>| {
+            matches!(ty.kind(), TyKind::Adt(adt, _) if self.tcx.is_diagnostic_item(sym::Vec, adt.did()))
+        };
+        if self.tcx.def_path_str(id) == "std::ops::Index::index" {
+            let (vec, idx) = (substs.type_at(0), substs.type_at(1));
+            (is_vec(vec) && idx == self.tcx.types.usize).then_some(BuiltinCallKind::VecIndex)
+        } else if self.tcx.item_name(id).as_str() == "len" {
+            let impl_id = self.tcx.impl_of_method(id)?;
+            let self_ty = self.tcx.type_of(impl_id);
+            (self.tcx.trait_id_of_impl(impl_id).is_none() && (is_vec(self_ty) || is_string(self.tcx, self_ty)))
+                .then_some(BuiltinCallKind::Len)
+        } else if self.tcx.item_name(id).as_str() == "get" {
+            let impl_id = self.tcx.impl_of_method(id)?;
+            let self_ty = self.tcx.type_of(impl_id);
+            (self.tcx.trait_id_of_impl(impl_id).is_none() && is_cell(self.tcx, self_ty))
+                .then_some(BuiltinCallKind::CellGet)
+        } else {
+            None
+        }
+    }
+    /// `a == b` and `a != b` on (references to) `String`s and `str`s.
+    fn string_eq(&self, id: DefId, substs: SubstsRef<'tcx>, args: &[PureExpression<'tcx>]) -> Option<PureExpression<'tcx>> {
+        let fn_name = self.tcx.def_path_str(id);
+        if !(fn_name == "std::cmp::PartialEq::eq" || fn_name == "std::cmp::PartialEq::ne")
+            || !substs.types().all(|ty| is_string(self.tcx, ty.peel_refs()))
+        {
+            return None;
+        }
+        assert_eq!(args.len(), 2);
+        let args = args.iter().map(|arg| arg.clone().deref(false)).collect();
+        let bool_ty = self.tcx.types.bool;
+        let eq = Expr::Call(CallInfo::Builtin(BuiltinCallKind::StrEq), args).with_ty(bool_ty);
+        Some(if fn_name.ends_with("::ne") {
+            Expr::UnOp(UnOpKind::UnOp(UnOp::Not), Box::new(eq)).with_ty(bool_ty)
+        } else {
+            eq
+        })
+    }
     fn is_id_special(&self, id: DefId) -> bool {
         self.tcx.crate_name(id.krate).to_string() == "russol_contracts"
     }
@@ -440,6 +598,11 @@ impl<'a, 'tcx> ThirTerm<'a, 'tcx> {
 enum RuslikStub {
     Snap,
     SetNew,
+    SeqNew,
+    SeqTake,
+    SeqSkip,
+    Elems,
+    Contents,
     Add,
     Sub,
     Eq,
@@ -459,7 +622,14 @@ impl RuslikStub {
             RuslikStub::Le => rustc_hir::BinOpKind::Le,
             RuslikStub::Gt => rustc_hir::BinOpKind::Gt,
             RuslikStub::Lt => rustc_hir::BinOpKind::Lt,
-            RuslikStub::In | RuslikStub::Snap | RuslikStub::SetNew => panic!(),
+            RuslikStub::In
+            | RuslikStub::Snap
+            | RuslikStub::SetNew
+            | RuslikStub::SeqNew
+            | RuslikStub::SeqTake
+            | RuslikStub::SeqSkip
+            | RuslikStub::Elems
+            | RuslikStub::Contents => panic!(),
         }
     }
     fn expect_deref(self) -> bool {
@@ -468,7 +638,32 @@ impl RuslikStub {
             RuslikStub::Eq | RuslikStub::Ge | RuslikStub::Le | RuslikStub::Gt | RuslikStub::Lt => {
                 true
             }
-            RuslikStub::In | RuslikStub::Snap | RuslikStub::SetNew => panic!(),
+            RuslikStub::In
+            | RuslikStub::Snap
+            | RuslikStub::SetNew
+            | RuslikStub::SeqNew
+            | RuslikStub::SeqTake
+            | RuslikStub::SeqSkip
+            | RuslikStub::Elems
+            | RuslikStub::Contents => panic!(),
         }
     }
 }
+
+/// `String` or `str`, which are abstract values in specifications.
+fn is_string<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+    match ty.kind() {
+        TyKind::Str => true,
+        TyKind::Adt(adt, _) => tcx.is_diagnostic_item(sym::String, adt.did()),
+        _ => false,
+    }
+}
+
+/// The string literal `expr` is (possibly reborrowed as `&*"..."`).
+fn str_lit(expr: &PureExpression) -> Option<Symbol> {
+    match expr.kind() {
+        Expr::Lit(LitKind::Str(lit, _)) => Some(*lit),
+        Expr::Field(inner, v, f) if (*f, *v) == ruslik_types::OLD => str_lit(inner),
+        _ => None,
+    }
+}
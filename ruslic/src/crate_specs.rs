@@ -0,0 +1,572 @@
+//! Specifications across crates. The `#[pure]` fns, extern specs and contracts of public fns
+//! of a crate are translated from its THIR, which is only available while compiling that crate.
+//! So they are written to a sidecar file next to its metadata (e.g.
+//! `target/debug/deps/libfoo-1234.russol.json`), and read back when a crate depending on it is
+//! synthesized. Imported extern specs and contracted fns are offered as components, imported
+//! pure fns can be called from specs like local ones.
+//!
+//! Types and `DefId`s are stored by their `DefPathHash`, which is stable across crates. Regions
+//! within spec expressions are already erased (they come from the THIR), and are stored as such.
+
+use rustc_ast::ast::{IntTy, LitIntType, LitKind, Mutability, StrStyle, UintTy};
+use rustc_data_structures::fingerprint::Fingerprint;
+use rustc_hir::{
+    def_id::{CrateNum, DefId, LOCAL_CRATE},
+    definitions::DefPathHash,
+    BinOpKind,
+};
+use rustc_middle::{
+    mir::{Field, UnOp},
+    ty::{subst::GenericArgKind, Const, GenericArg, ParamEnv, ParamTy, Ty, TyCtxt, TyKind},
+};
+use rustc_session::output::filename_for_metadata;
+use rustc_span::Symbol;
+use rustc_target::abi::VariantIdx;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    hir_translator::{extern_path, HirTranslator, PureFn},
+    ruslik_pure::{BuiltinCallKind, CallInfo, ExprKind, PureExpression, UnOpKind},
+    ruslik_ssl::Var,
+    ruslik_types::{AdtIdent, RuslikFnSig},
+    std_specs::is_std_spec,
+};
+
+const EXTENSION: &str = "russol.json";
+
+#[derive(Serialize, Deserialize)]
+struct CrateSpecs {
+    /// To check that the file belongs to the crate it was found next to
+    stable_crate_id: u64,
+    pure_fns: Vec<PureFnSpec>,
+    /// Extern specs and the contracts of public fns, both used as extern fns when imported
+    fn_specs: Vec<FnSpec>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PureFnSpec {
+    def_id: SDefId,
+    arg_names: Vec<String>,
+    expr: SExpr,
+    pure_post: SExpr,
+    executable: bool,
+    ast_nodes: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FnSpec {
+    def_id: SDefId,
+    pure_pre: SExpr,
+    pure_post: SExpr,
+    ast_nodes: usize,
+}
+
+/// A `DefPathHash`
+#[derive(Serialize, Deserialize)]
+struct SDefId(u64, u64);
+
+#[derive(Serialize, Deserialize)]
+enum STy {
+    /// `bool`, `char`, the integer and float types, `str` and `!`, by name
+    Prim(String),
+    Adt(SDefId, Vec<SArg>),
+    Ref(Box<STy>, bool),
+    Tuple(Vec<STy>),
+    Slice(Box<STy>),
+    Array(Box<STy>, u64),
+    Param(u32, String),
+}
+
+#[derive(Serialize, Deserialize)]
+enum SArg {
+    Ty(STy),
+    /// Erased, other than `'static`
+    Region(bool),
+}
+
+#[derive(Serialize, Deserialize)]
+struct SExpr {
+    ty: STy,
+    kind: SExprKind,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SExprKind {
+    Never,
+    Var(String),
+    Bool(bool),
+    Str(String),
+    /// With the suffix, if any
+    Int(u128, Option<String>),
+    BinOp(String, Box<SExpr>, Box<SExpr>),
+    UnOp(SUnOp, Box<SExpr>),
+    Constructor(String, u32, Vec<(u32, SExpr)>),
+    Field(Box<SExpr>, u32, u32),
+    IfElse(Box<SExpr>, Box<SExpr>, Box<SExpr>),
+    Call(SCall, Vec<SExpr>),
+}
+
+#[derive(Serialize, Deserialize)]
+enum SUnOp {
+    Snap,
+    Not,
+    Neg,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SCall {
+    Pure(SDefId, Vec<SArg>),
+    SetConstruct,
+    SetContains,
+    Len,
+    VecElems,
+    VecIndex,
+    SeqConstruct,
+    SeqTake,
+    SeqSkip,
+    StrEq,
+    CellGet,
+}
+
+const BIN_OPS: [BinOpKind; 18] = [
+    BinOpKind::Add,
+    BinOpKind::Sub,
+    BinOpKind::Mul,
+    BinOpKind::Div,
+    BinOpKind::Rem,
+    BinOpKind::And,
+    BinOpKind::Or,
+    BinOpKind::BitXor,
+    BinOpKind::BitAnd,
+    BinOpKind::BitOr,
+    BinOpKind::Shl,
+    BinOpKind::Shr,
+    BinOpKind::Eq,
+    BinOpKind::Lt,
+    BinOpKind::Le,
+    BinOpKind::Ne,
+    BinOpKind::Ge,
+    BinOpKind::Gt,
+];
+const INT_TYS: [IntTy; 6] = [
+    IntTy::Isize,
+    IntTy::I8,
+    IntTy::I16,
+    IntTy::I32,
+    IntTy::I64,
+    IntTy::I128,
+];
+const UINT_TYS: [UintTy; 6] = [
+    UintTy::Usize,
+    UintTy::U8,
+    UintTy::U16,
+    UintTy::U32,
+    UintTy::U64,
+    UintTy::U128,
+];
+
+/// Writes the specs of the local crate next to its metadata. Anything which cannot be encoded
+/// (e.g. uses a closure type) is left out.
+pub(crate) fn export<'tcx>(tcx: TyCtxt<'tcx>, translator: &HirTranslator<'tcx>) {
+    let enc = Encoder { tcx };
+    let pure_fns = translator
+        .pure_fns
+        .values()
+        .filter_map(|pure_fn| enc.pure_fn(pure_fn))
+        .collect();
+    let extern_fns = translator.extern_fns.iter().filter(|sig| {
+        !is_std_spec(tcx, sig.def_id)
+            && (is_reachable(tcx, sig.def_id) || extern_path(tcx, sig.def_id).is_some())
+    });
+    let contracted_fns = translator
+        .impure_fns
+        .iter()
+        .filter(|f| f.sketch.is_none() && is_exported(tcx, f.sig.def_id))
+        .filter(|f| !f.sig.pure_pre.is_true() || !f.sig.pure_post.is_true())
+        .map(|f| &f.sig);
+    let fn_specs = extern_fns
+        .chain(contracted_fns)
+        .filter_map(|sig| enc.fn_spec(sig))
+        .collect();
+    let specs = CrateSpecs {
+        stable_crate_id: tcx.sess.local_stable_crate_id().to_u64(),
+        pure_fns,
+        fn_specs,
+    };
+    let crate_name = tcx.crate_name(LOCAL_CRATE);
+    let path = filename_for_metadata(tcx.sess, crate_name.as_str(), tcx.output_filenames(()))
+        .with_extension(EXTENSION);
+    if let Err(err) = std::fs::write(&path, serde_json::to_string(&specs).unwrap()) {
+        tcx.sess.warn(&format!(
+            "could not write specs to {}: {err}",
+            path.to_string_lossy()
+        ));
+    }
+}
+
+/// Whether downstream crates can name `def_id`: it is `pub` and so are all the modules it is in
+/// (or it is re-exported from a public path).
+fn is_reachable(tcx: TyCtxt, def_id: DefId) -> bool {
+    def_id
+        .as_local()
+        .map_or(false, |id| tcx.privacy_access_levels(()).is_exported(id))
+}
+
+/// Reachable free fns and inherent methods, which downstream crates can call by path.
+fn is_exported(tcx: TyCtxt, def_id: DefId) -> bool {
+    is_reachable(tcx, def_id)
+        && tcx
+            .impl_of_method(def_id)
+            .map_or(true, |impl_id| tcx.trait_id_of_impl(impl_id).is_none())
+}
+
+/// Adds the specs of all dependencies which were compiled by `ruslic` to `translator`.
+pub(crate) fn import<'tcx>(tcx: TyCtxt<'tcx>, translator: &mut HirTranslator<'tcx>) {
+    for &cnum in tcx.crates(()) {
+        let source = tcx.used_crate_source(cnum);
+        let specs = source.paths().find_map(|path| {
+            let content = std::fs::read_to_string(path.with_extension(EXTENSION)).ok()?;
+            serde_json::from_str::<CrateSpecs>(&content).ok()
+        });
+        let specs = match specs {
+            Some(specs) if specs.stable_crate_id == tcx.stable_crate_id(cnum).to_u64() => specs,
+            _ => continue,
+        };
+        let dec = Decoder { tcx, cnum };
+        for pure_fn in &specs.pure_fns {
+            let pure_fn = dec.pure_fn(pure_fn);
+            translator.pure_fns.insert(pure_fn.def_id, pure_fn);
+        }
+        for fn_spec in &specs.fn_specs {
+            translator.extern_fns.push(dec.fn_spec(fn_spec));
+        }
+    }
+}
+
+/// The path by which a fn from another crate is called, without generic args (e.g.
+/// `foo::List::new` for `foo::List::<T>::new`).
+pub(crate) fn call_path(tcx: TyCtxt, def_id: DefId) -> String {
+    let mut depth = 0;
+    let path: String = tcx
+        .def_path_str(def_id)
+        .chars()
+        .filter(|&c| {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => return depth == 0,
+            }
+            false
+        })
+        .collect();
+    path.replace("::::", "::")
+}
+
+struct Encoder<'tcx> {
+    tcx: TyCtxt<'tcx>,
+}
+impl<'tcx> Encoder<'tcx> {
+    fn def_id(&self, def_id: DefId) -> SDefId {
+        let (a, b) = self.tcx.def_path_hash(def_id).0.as_value();
+        SDefId(a, b)
+    }
+
+    fn pure_fn(&self, pure_fn: &PureFn<'tcx>) -> Option<PureFnSpec> {
+        Some(PureFnSpec {
+            def_id: self.def_id(pure_fn.def_id),
+            arg_names: pure_fn.arg_names.iter().map(Var::uuid).collect(),
+            expr: self.expr(&pure_fn.expr)?,
+            pure_post: self.expr(&pure_fn.pure_post)?,
+            executable: pure_fn.executable,
+            ast_nodes: pure_fn.ast_nodes,
+        })
+    }
+
+    fn fn_spec(&self, sig: &RuslikFnSig<'tcx>) -> Option<FnSpec> {
+        Some(FnSpec {
+            def_id: self.def_id(sig.def_id),
+            pure_pre: self.expr(&sig.pure_pre)?,
+            pure_post: self.expr(&sig.pure_post)?,
+            ast_nodes: sig.ast_nodes,
+        })
+    }
+
+    fn ty(&self, ty: Ty<'tcx>) -> Option<STy> {
+        Some(match ty.kind() {
+            TyKind::Bool
+            | TyKind::Char
+            | TyKind::Int(_)
+            | TyKind::Uint(_)
+            | TyKind::Float(_)
+            | TyKind::Str
+            | TyKind::Never => STy::Prim(ty.to_string()),
+            TyKind::Adt(adt, substs) => STy::Adt(self.def_id(adt.did()), self.args(substs)?),
+            TyKind::Ref(_, ty, mutbl) => {
+                STy::Ref(Box::new(self.ty(*ty)?), *mutbl == Mutability::Mut)
+            }
+            TyKind::Tuple(tys) => {
+                STy::Tuple(tys.iter().map(|ty| self.ty(ty)).collect::<Option<_>>()?)
+            }
+            TyKind::Slice(ty) => STy::Slice(Box::new(self.ty(*ty)?)),
+            TyKind::Array(ty, len) => STy::Array(
+                Box::new(self.ty(*ty)?),
+                len.try_eval_usize(self.tcx, ParamEnv::empty())?,
+            ),
+            TyKind::Param(p) => STy::Param(p.index, p.name.to_string()),
+            _ => return None,
+        })
+    }
+
+    fn args(&self, substs: &[GenericArg<'tcx>]) -> Option<Vec<SArg>> {
+        substs
+            .iter()
+            .map(|arg| match arg.unpack() {
+                GenericArgKind::Type(ty) => self.ty(ty).map(SArg::Ty),
+                GenericArgKind::Lifetime(r) => Some(SArg::Region(r.is_static())),
+                GenericArgKind::Const(_) => None,
+            })
+            .collect()
+    }
+
+    fn expr(&self, expr: &PureExpression<'tcx>) -> Option<SExpr> {
+        let boxed = |expr| self.expr(expr).map(Box::new);
+        let kind = match expr.kind() {
+            ExprKind::Never => SExprKind::Never,
+            ExprKind::Var(var) => SExprKind::Var(var.uuid()),
+            ExprKind::Lit(LitKind::Bool(b)) => SExprKind::Bool(*b),
+            ExprKind::Lit(LitKind::Str(s, _)) => SExprKind::Str(s.to_string()),
+            ExprKind::Lit(LitKind::Int(i, suffix)) => SExprKind::Int(
+                *i,
+                match suffix {
+                    LitIntType::Signed(ty) => Some(ty.name_str().to_string()),
+                    LitIntType::Unsigned(ty) => Some(ty.name_str().to_string()),
+                    LitIntType::Unsuffixed => None,
+                },
+            ),
+            ExprKind::Lit(_) => return None,
+            ExprKind::BinOp(op, lhs, rhs) => {
+                SExprKind::BinOp(op.as_str().to_string(), boxed(lhs)?, boxed(rhs)?)
+            }
+            ExprKind::UnOp(op, inner) => {
+                let op = match op {
+                    UnOpKind::Snap => SUnOp::Snap,
+                    UnOpKind::UnOp(UnOp::Not) => SUnOp::Not,
+                    UnOpKind::UnOp(UnOp::Neg) => SUnOp::Neg,
+                };
+                SExprKind::UnOp(op, boxed(inner)?)
+            }
+            ExprKind::Constructor(adt, variant, fields) => SExprKind::Constructor(
+                adt.to_string(),
+                variant.as_u32(),
+                fields
+                    .iter()
+                    .map(|(f, e)| Some((f.as_u32(), self.expr(e)?)))
+                    .collect::<Option<_>>()?,
+            ),
+            ExprKind::Field(inner, variant, field) => {
+                SExprKind::Field(boxed(inner)?, variant.as_u32(), field.as_u32())
+            }
+            ExprKind::IfElse(c, t, e) => SExprKind::IfElse(boxed(c)?, boxed(t)?, boxed(e)?),
+            ExprKind::Call(call, args) => {
+                let call = match call {
+                    CallInfo::Pure(id, substs) => SCall::Pure(self.def_id(*id), self.args(substs)?),
+                    CallInfo::Builtin(BuiltinCallKind::SetConstruct) => SCall::SetConstruct,
+                    CallInfo::Builtin(BuiltinCallKind::SetContains) => SCall::SetContains,
+                    CallInfo::Builtin(BuiltinCallKind::Len) => SCall::Len,
+                    CallInfo::Builtin(BuiltinCallKind::VecElems) => SCall::VecElems,
+                    CallInfo::Builtin(BuiltinCallKind::VecIndex) => SCall::VecIndex,
+                    CallInfo::Builtin(BuiltinCallKind::SeqConstruct) => SCall::SeqConstruct,
+                    CallInfo::Builtin(BuiltinCallKind::SeqTake) => SCall::SeqTake,
+                    CallInfo::Builtin(BuiltinCallKind::SeqSkip) => SCall::SeqSkip,
+                    CallInfo::Builtin(BuiltinCallKind::StrEq) => SCall::StrEq,
+                    CallInfo::Builtin(BuiltinCallKind::CellGet) => SCall::CellGet,
+                };
+                let args = args
+                    .iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Option<_>>()?;
+                SExprKind::Call(call, args)
+            }
+        };
+        Some(SExpr {
+            ty: self.ty(expr.ty())?,
+            kind,
+        })
+    }
+}
+
+struct Decoder<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    /// The crate the file belongs to, for error messages
+    cnum: CrateNum,
+}
+impl<'tcx> Decoder<'tcx> {
+    fn def_id(&self, def_id: &SDefId) -> DefId {
+        let hash = DefPathHash(Fingerprint::new(def_id.0, def_id.1));
+        self.tcx.def_path_hash_to_def_id(hash, &mut || {
+            panic!(
+                "stale specs of crate `{}`, rebuild it",
+                self.tcx.crate_name(self.cnum)
+            )
+        })
+    }
+
+    fn pure_fn(&self, pure_fn: &PureFnSpec) -> PureFn<'tcx> {
+        PureFn {
+            def_id: self.def_id(&pure_fn.def_id),
+            arg_names: pure_fn
+                .arg_names
+                .iter()
+                .map(|name| Var::new(name))
+                .collect(),
+            expr: self.expr(&pure_fn.expr),
+            pure_post: self.expr(&pure_fn.pure_post),
+            executable: pure_fn.executable,
+            ast_nodes: pure_fn.ast_nodes,
+        }
+    }
+
+    fn fn_spec(&self, fn_spec: &FnSpec) -> RuslikFnSig<'tcx> {
+        RuslikFnSig::new(
+            self.def_id(&fn_spec.def_id),
+            self.tcx,
+            self.expr(&fn_spec.pure_pre),
+            self.expr(&fn_spec.pure_post),
+            String::new(),
+            fn_spec.ast_nodes,
+        )
+    }
+
+    fn ty(&self, ty: &STy) -> Ty<'tcx> {
+        let tcx = self.tcx;
+        match ty {
+            STy::Prim(name) => {
+                let types = &tcx.types;
+                [
+                    types.bool,
+                    types.char,
+                    types.isize,
+                    types.i8,
+                    types.i16,
+                    types.i32,
+                    types.i64,
+                    types.i128,
+                    types.usize,
+                    types.u8,
+                    types.u16,
+                    types.u32,
+                    types.u64,
+                    types.u128,
+                    types.f32,
+                    types.f64,
+                    types.str_,
+                    types.never,
+                ]
+                .into_iter()
+                .find(|ty| ty.to_string() == *name)
+                .unwrap()
+            }
+            STy::Adt(def_id, substs) => {
+                tcx.mk_adt(tcx.adt_def(self.def_id(def_id)), self.args(substs))
+            }
+            STy::Ref(ty, mutbl) => {
+                let ty = self.ty(ty);
+                let r = tcx.lifetimes.re_erased;
+                if *mutbl {
+                    tcx.mk_mut_ref(r, ty)
+                } else {
+                    tcx.mk_imm_ref(r, ty)
+                }
+            }
+            STy::Tuple(tys) => tcx.mk_tup(tys.iter().map(|ty| self.ty(ty))),
+            STy::Slice(ty) => tcx.mk_slice(self.ty(ty)),
+            STy::Array(ty, len) => {
+                tcx.mk_ty(TyKind::Array(self.ty(ty), Const::from_usize(tcx, *len)))
+            }
+            STy::Param(index, name) => ParamTy::new(*index, Symbol::intern(name)).to_ty(tcx),
+        }
+    }
+
+    fn args(&self, substs: &[SArg]) -> rustc_middle::ty::SubstsRef<'tcx> {
+        self.tcx
+            .mk_substs(substs.iter().map(|arg| -> GenericArg<'tcx> {
+                match arg {
+                    SArg::Ty(ty) => self.ty(ty).into(),
+                    SArg::Region(true) => self.tcx.lifetimes.re_static.into(),
+                    SArg::Region(false) => self.tcx.lifetimes.re_erased.into(),
+                }
+            }))
+    }
+
+    fn expr(&self, expr: &SExpr) -> PureExpression<'tcx> {
+        let boxed = |expr| Box::new(self.expr(expr));
+        let kind = match &expr.kind {
+            SExprKind::Never => ExprKind::Never,
+            SExprKind::Var(name) => ExprKind::Var(Var::new(name)),
+            SExprKind::Bool(b) => ExprKind::Lit(LitKind::Bool(*b)),
+            SExprKind::Str(s) => ExprKind::Lit(LitKind::Str(Symbol::intern(s), StrStyle::Cooked)),
+            SExprKind::Int(i, suffix) => {
+                let suffix = match suffix.as_deref() {
+                    None => LitIntType::Unsuffixed,
+                    Some(name) => INT_TYS
+                        .into_iter()
+                        .find(|ty| ty.name_str() == name)
+                        .map(LitIntType::Signed)
+                        .or_else(|| {
+                            UINT_TYS
+                                .into_iter()
+                                .find(|ty| ty.name_str() == name)
+                                .map(LitIntType::Unsigned)
+                        })
+                        .unwrap(),
+                };
+                ExprKind::Lit(LitKind::Int(*i, suffix))
+            }
+            SExprKind::BinOp(op, lhs, rhs) => {
+                let op = BIN_OPS.into_iter().find(|o| o.as_str() == op).unwrap();
+                ExprKind::BinOp(op, boxed(lhs), boxed(rhs))
+            }
+            SExprKind::UnOp(op, inner) => {
+                let op = match op {
+                    SUnOp::Snap => UnOpKind::Snap,
+                    SUnOp::Not => UnOpKind::UnOp(UnOp::Not),
+                    SUnOp::Neg => UnOpKind::UnOp(UnOp::Neg),
+                };
+                ExprKind::UnOp(op, boxed(inner))
+            }
+            SExprKind::Constructor(adt, variant, fields) => ExprKind::Constructor(
+                AdtIdent::intern(adt),
+                VariantIdx::from_u32(*variant),
+                fields
+                    .iter()
+                    .map(|(f, e)| (Field::from_u32(*f), self.expr(e)))
+                    .collect(),
+            ),
+            SExprKind::Field(inner, variant, field) => ExprKind::Field(
+                boxed(inner),
+                VariantIdx::from_u32(*variant),
+                Field::from_u32(*field),
+            ),
+            SExprKind::IfElse(c, t, e) => ExprKind::IfElse(boxed(c), boxed(t), boxed(e)),
+            SExprKind::Call(call, args) => {
+                let call = match call {
+                    SCall::Pure(def_id, substs) => {
+                        CallInfo::Pure(self.def_id(def_id), self.args(substs))
+                    }
+                    SCall::SetConstruct => CallInfo::Builtin(BuiltinCallKind::SetConstruct),
+                    SCall::SetContains => CallInfo::Builtin(BuiltinCallKind::SetContains),
+                    SCall::Len => CallInfo::Builtin(BuiltinCallKind::Len),
+                    SCall::VecElems => CallInfo::Builtin(BuiltinCallKind::VecElems),
+                    SCall::VecIndex => CallInfo::Builtin(BuiltinCallKind::VecIndex),
+                    SCall::SeqConstruct => CallInfo::Builtin(BuiltinCallKind::SeqConstruct),
+                    SCall::SeqTake => CallInfo::Builtin(BuiltinCallKind::SeqTake),
+                    SCall::SeqSkip => CallInfo::Builtin(BuiltinCallKind::SeqSkip),
+                    SCall::StrEq => CallInfo::Builtin(BuiltinCallKind::StrEq),
+                    SCall::CellGet => CallInfo::Builtin(BuiltinCallKind::CellGet),
+                };
+                ExprKind::Call(call, args.iter().map(|arg| self.expr(arg)).collect())
+            }
+        };
+        kind.with_ty(self.ty(&expr.ty))
+    }
+}
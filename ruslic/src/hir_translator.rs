@@ -6,11 +6,20 @@ use rustc_hir::{
 };
 use rustc_middle::{
     thir::{ClosureExpr, Expr, ExprId, ExprKind, Stmt, StmtKind, Thir},
-    ty::{DefIdTree, TyCtxt, TyKind, WithOptConstParam},
+    ty::{AssocKind, DefIdTree, InternalSubsts, TyCtxt, TyKind, WithOptConstParam},
 };
+use rustc_span::{Span, Symbol};
 
 use crate::ruslik_ssl::Var;
-use crate::{contract_translator::to_expr, ruslik_pure::PureExpression, ruslik_types::RuslikFnSig};
+use crate::{
+    closure,
+    contract_translator::to_expr,
+    ruslik_pure::{ExprKind as PureExprKind, PureExpression},
+    ruslik_pure_helpers::PureExpressionWalker,
+    ruslik_types::RuslikFnSig,
+    sketch::{outer_macro_call, SketchCollector},
+    subst_generics::{SubstFolder, TyFoldable},
+};
 
 #[derive(Debug, Clone)]
 pub struct PureFn<'tcx> {
@@ -23,6 +32,22 @@ pub struct PureFn<'tcx> {
 }
 
 pub type PureFnMap<'tcx> = FxHashMap<DefId, PureFn<'tcx>>;
+/// The specs of trait method declarations, which their impls inherit
+pub type TraitSpecMap<'tcx> = FxHashMap<DefId, RuslikFnSig<'tcx>>;
+
+#[derive(Clone)]
+pub struct ImpureFn<'tcx> {
+    /// Annotated with `#[synth]`
+    pub is_synth: bool,
+    /// The body is only a `ruslik!()`, `todo!()` or `unimplemented!()`
+    pub is_hole: bool,
+    /// For a `ruslik!()` hole within a written body, its span. The `sig` is then that of the hole.
+    pub sketch: Option<Span>,
+    pub sig: RuslikFnSig<'tcx>,
+}
+
+/// Macros which mark a fn body as a hole to be synthesized.
+const HOLE_MACROS: [&str; 3] = ["ruslik", "todo", "unimplemented"];
 
 #[derive(Clone, Copy, Eq, PartialEq)]
 enum SpecKind {
@@ -31,11 +56,26 @@ enum SpecKind {
     TrustedEnsures,
 }
 
+/// The path given as `#[extern_spec(path)]`, which solutions should call instead of `def_id`.
+pub(crate) fn extern_path(tcx: TyCtxt<'_>, def_id: DefId) -> Option<String> {
+    tcx.get_attrs_unchecked(def_id).iter().find_map(|a| {
+        if let AttrKind::Normal(p) = &a.kind
+            && let AttrItem { path, args: MacArgs::Eq(_, MacArgsEq::Hir(l)), .. } = &p.item
+            && path.segments.last().unwrap().ident.as_str() == "ruslik_extern_path"
+        {
+            Some(l.token_lit.symbol.to_string())
+        } else {
+            None
+        }
+    })
+}
+
 pub struct HirTranslator<'tcx> {
     tcx: TyCtxt<'tcx>,
     pub pure_fns: PureFnMap<'tcx>,
     pub extern_fns: Vec<RuslikFnSig<'tcx>>,
-    pub impure_fns: Vec<(bool, RuslikFnSig<'tcx>)>,
+    pub impure_fns: Vec<ImpureFn<'tcx>>,
+    pub trait_specs: TraitSpecMap<'tcx>,
     // Type predicates
     // types: FxHashMap<DefId, RusType<'tcx>>,
     // basic_types: FxHashSet<RusType<'tcx>>,
@@ -47,6 +87,7 @@ impl<'tcx> HirTranslator<'tcx> {
             pure_fns: FxHashMap::default(),
             extern_fns: Vec::new(),
             impure_fns: Vec::new(),
+            trait_specs: FxHashMap::default(),
             // types: FxHashMap::default(),
             // basic_types: FxHashSet::default(),
         }
@@ -54,21 +95,23 @@ impl<'tcx> HirTranslator<'tcx> {
 
     pub fn translate(&mut self, def_id: DefId) -> Option<()> {
         match self.tcx.def_kind(def_id) {
-            DefKind::AssocFn | DefKind::Fn => {
+            DefKind::AssocFn | DefKind::Fn | DefKind::Closure => {
+                // The hidden methods holding the specs of trait method declarations come from a
+                // macro, so they must be collected before the check below
+                let parent = self
+                    .tcx
+                    .opt_parent(def_id)
+                    .map(|parent| self.tcx.def_kind(parent));
+                if parent == Some(DefKind::Trait) && let Some(spec_of) = self.trait_spec_of(def_id) {
+                    return self.translate_trait_spec(def_id, spec_of);
+                }
                 // Don't try to synth derived fns
                 let span: rustc_span::Span = self.tcx.def_span(def_id);
                 if span.from_expansion() {
                     return None;
                 }
-                // Don't try to synth default trait fns
-                let parent = self
-                    .tcx
-                    .opt_parent(def_id)
-                    .map(|parent| self.tcx.def_kind(parent));
-                if matches!(
-                    parent,
-                    Some(DefKind::Trait) | Some(DefKind::Fn) | Some(DefKind::AssocFn)
-                ) {
+                let is_closure = self.tcx.is_closure(def_id);
+                if is_closure && !closure::is_closure(self.tcx, def_id) {
                     return None;
                 }
 
@@ -96,7 +139,13 @@ impl<'tcx> HirTranslator<'tcx> {
                         }
                     }
                 }
-                let (pure_pre, pure_post, ast_nodes) = self.collect_contracts(def_id, is_pure)?;
+                let (pure_pre, pure_post, ast_nodes, is_hole) =
+                    self.collect_contracts(def_id, is_pure)?;
+                // Only closures which were given specs or left as holes are synthesized, their
+                // captured variables are extra args
+                if is_closure && !is_hole && self.parse_attr_count(def_id).is_none() {
+                    return None;
+                }
                 if is_pure {
                     assert!(
                         !is_extern,
@@ -140,26 +189,128 @@ impl<'tcx> HirTranslator<'tcx> {
                             params
                         );
                     }
-                    self.tcx.ensure().mir_borrowck(def_id.expect_local());
                     let sig =
                         RuslikFnSig::new(def_id, self.tcx, pure_pre, pure_post, params, ast_nodes);
+                    // Default trait fns are synthesized with `Self: Trait` as the only thing known
+                    // about `Self`, their specs also apply to every impl which doesn't override them
+                    if parent == Some(DefKind::Trait) && self.parse_attr_count(def_id).is_some() {
+                        self.trait_specs.insert(def_id, sig.clone());
+                    }
+                    // Before borrowck, which steals the THIR
+                    let sketches = if is_extern || is_hole || is_closure {
+                        Vec::new()
+                    } else {
+                        let specs = self.parse_attr_count(def_id).unwrap_or(0);
+                        SketchCollector::collect(self.tcx, &sig, specs)
+                    };
+                    self.tcx.ensure().mir_borrowck(def_id.expect_local());
                     if is_extern {
                         self.extern_fns.push(sig);
+                    } else if sketches.is_empty() {
+                        self.impure_fns.push(ImpureFn {
+                            is_synth,
+                            is_hole,
+                            sketch: None,
+                            sig,
+                        });
                     } else {
-                        self.impure_fns.push((is_synth, sig));
+                        // Only fill in the holes, rather than synthesizing the whole fn
+                        self.impure_fns
+                            .extend(sketches.into_iter().map(|sketch| ImpureFn {
+                                is_synth,
+                                is_hole: true,
+                                sketch: Some(sketch.span),
+                                sig: sketch.sig,
+                            }));
                     }
                 }
             }
-            DefKind::Closure
-            | DefKind::AnonConst
-            | DefKind::Const
-            | DefKind::Static(_)
-            | DefKind::AssocConst => (),
+            DefKind::AnonConst | DefKind::Const | DefKind::Static(_) | DefKind::AssocConst => (),
             other => println!("Skipping {:?}", other),
         }
         Some(())
     }
 
+    /// The name of the trait method whose specs are held by this hidden `__russol_spec_<name>`
+    /// method, which `#[requires]`/`#[ensures]` generate for a declaration without a body.
+    fn trait_spec_of(&self, def_id: DefId) -> Option<Symbol> {
+        self.tcx.get_attrs_unchecked(def_id).iter().find_map(|a| {
+            if let AttrKind::Normal(p) = &a.kind
+                && let AttrItem { path, args: MacArgs::Eq(_, MacArgsEq::Hir(l)), .. } = &p.item
+                && path.segments.last().unwrap().ident.as_str() == "ruslik_trait_spec"
+            {
+                Some(l.token_lit.symbol)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn translate_trait_spec(&mut self, def_id: DefId, spec_of: Symbol) -> Option<()> {
+        let trait_id = self.tcx.parent(def_id);
+        let method_id = self
+            .tcx
+            .associated_items(trait_id)
+            .filter_by_name_unhygienic(spec_of)
+            .find(|item| item.kind == AssocKind::Fn)?
+            .def_id;
+        let (pure_pre, pure_post, ast_nodes, _) = self.collect_contracts(def_id, false)?;
+        let sig = RuslikFnSig::new(
+            method_id,
+            self.tcx,
+            pure_pre,
+            pure_post,
+            String::new(),
+            ast_nodes,
+        );
+        self.trait_specs.insert(method_id, sig);
+        Some(())
+    }
+
+    /// Impls of trait methods without specs of their own are synthesized against the specs of
+    /// the trait method, with `Self` and the trait's generics substituted.
+    pub fn inherit_trait_specs(&mut self) {
+        for ImpureFn { sketch, sig, .. } in &mut self.impure_fns {
+            if sketch.is_some() || !sig.pure_pre.is_true() || !sig.pure_post.is_true() {
+                continue;
+            }
+            let trait_spec = self
+                .tcx
+                .opt_associated_item(sig.def_id)
+                .and_then(|item| item.trait_item_def_id)
+                .and_then(|id| self.trait_specs.get(&id));
+            let trait_spec = match trait_spec {
+                Some(trait_spec) => trait_spec,
+                None => continue,
+            };
+            let impl_id = self.tcx.parent(sig.def_id);
+            let trait_ref = self.tcx.impl_trait_ref(impl_id).unwrap();
+            let substs = InternalSubsts::identity_for_item(self.tcx, sig.def_id).rebase_onto(
+                self.tcx,
+                impl_id,
+                trait_ref.substs,
+            );
+            let mut folder = SubstFolder::from_substs_ref(self.tcx, substs);
+            let mut rename = RenameArgs(
+                trait_spec
+                    .args
+                    .iter()
+                    .zip(&sig.args)
+                    .map(|((from, _), (to, _))| (*from, *to))
+                    .collect(),
+            );
+            for (spec, expr) in [
+                (&trait_spec.pure_pre, &mut sig.pure_pre),
+                (&trait_spec.pure_post, &mut sig.pure_post),
+            ] {
+                *expr = spec.clone();
+                expr.subst(&mut folder);
+                rename.walk_expr_mut(expr);
+            }
+            sig.ast_nodes += trait_spec.ast_nodes;
+        }
+    }
+
     fn parse_attr_count(&self, def_id: DefId) -> Option<usize> {
         self.tcx.get_attrs_unchecked(def_id).iter().find_map(|a| {
             if let AttrKind::Normal(p) = &a.kind {
@@ -191,8 +342,8 @@ impl<'tcx> HirTranslator<'tcx> {
         &self,
         def_id: DefId,
         is_pure: bool,
-    ) -> Option<(PureExpression<'tcx>, PureExpression<'tcx>, usize)> {
-        let (thir, _) = self
+    ) -> Option<(PureExpression<'tcx>, PureExpression<'tcx>, usize, bool)> {
+        let (thir, body) = self
             .tcx
             .thir_body(WithOptConstParam::unknown(def_id.expect_local()))
             .unwrap();
@@ -203,7 +354,7 @@ impl<'tcx> HirTranslator<'tcx> {
         }
         let thir = &thir.borrow();
         let mut contracts_len = 0;
-        let contract = thir
+        let (pure_pre, pure_post, ast_nodes) = thir
             .stmts
             .iter()
             .map_while(|stmt| self.parse_spec_stmt(stmt, thir))
@@ -239,7 +390,36 @@ impl<'tcx> HirTranslator<'tcx> {
             );
             // if !is_pure { println!("Found no contract for {}. Unconstrained synthesis is generally uninteresting!", self.tcx.item_name(def_id)); }
         }
-        Some(contract)
+        let is_hole = Self::is_hole(body, thir, contracts_len);
+        Some((pure_pre, pure_post, ast_nodes, is_hole))
+    }
+    /// Is the body just the specs followed by a `ruslik!()`, `todo!()` or `unimplemented!()`
+    fn is_hole(body: ExprId, thir: &Thir, contracts_len: usize) -> bool {
+        let mut body = &thir.exprs[body];
+        // A diverging body is wrapped in a `NeverToAny`, and in an unsizing `Pointer` cast if it
+        // returns e.g. a `Box<dyn Trait>`
+        while let ExprKind::Scope { value: inner, .. }
+        | ExprKind::NeverToAny { source: inner }
+        | ExprKind::Pointer { source: inner, .. } = body.kind
+        {
+            body = &thir.exprs[inner];
+        }
+        let block = match body.kind {
+            ExprKind::Block { block } => &thir.blocks[block],
+            _ => return false,
+        };
+        let hole = match (block.stmts.len().checked_sub(contracts_len), block.expr) {
+            (Some(0), Some(expr)) => expr,
+            (Some(1), None) => match thir.stmts[*block.stmts.last().unwrap()].kind {
+                StmtKind::Expr { expr, .. } => expr,
+                _ => return false,
+            },
+            _ => return false,
+        };
+        matches!(
+            outer_macro_call(thir.exprs[hole].span),
+            Some((name, _)) if HOLE_MACROS.contains(&name.as_str())
+        )
     }
     fn parse_spec_stmt(&self, stmt: &Stmt, thir: &Thir) -> Option<(SpecKind, LocalDefId)> {
         if let StmtKind::Expr { expr, .. } = stmt.kind {
@@ -281,3 +461,18 @@ impl<'tcx> HirTranslator<'tcx> {
         }
     }
 }
+
+/// Renames the args of a trait method to those of an impl of it.
+struct RenameArgs(FxHashMap<Var, Var>);
+impl<'tcx> PureExpressionWalker<'tcx> for RenameArgs {
+    fn walk_kind_mut(&mut self, k: &mut PureExprKind<'tcx>) {
+        match k {
+            PureExprKind::Var(var) => {
+                if let Some(new) = self.0.get(var) {
+                    *var = *new;
+                }
+            }
+            _ => k.walk_mut(self),
+        }
+    }
+}
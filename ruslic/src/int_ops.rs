@@ -0,0 +1,553 @@
+//! Rust's integer operations in terms of the mathematical integers which SuSLik reasons about.
+//! Integers are constrained to the exact range of their type (see `Expr::int_bounds`), so
+//! wrapping needs at most one correction of the modulus in each direction, or a `%` when
+//! truncating, which keeps everything linear. This is used for `as` casts, `!`, `/`, `%`, shifts
+//! by a literal and calls such as `a.wrapping_add(b)` in specs, and for the built-in specs of the
+//! integer operations that synthesis can use as components.
+//!
+//! `/` and `%` truncate towards zero in Rust, they are emitted with non-negative operands where
+//! that agrees with the euclidean semantics of SMT. A shift right is a division which rounds
+//! down, which is what the euclidean one does with a positive divisor.
+
+use rustc_ast::ast::LitKind;
+use rustc_hir::{def_id::DefId, BinOpKind, LangItem};
+use rustc_middle::{
+    mir::Field,
+    ty::{
+        fast_reject::{simplify_type, TreatParams},
+        subst::SubstsRef,
+        AssocKind, EarlyBinder, ParamEnv, Subst, Ty, TyCtxt, TyKind,
+    },
+};
+use rustc_span::{sym, Symbol};
+
+use crate::{
+    ruslik_pure::{ExprKind, PureExpression},
+    ruslik_ssl::Var,
+    ruslik_types::RuslikFnSig,
+    suslik::Expr,
+};
+
+/// The width in bits and the signedness of an integer type, `isize` and `usize` are as wide as
+/// a pointer on the target.
+pub(crate) fn int_width(tcx: TyCtxt, ty: Ty) -> Option<(u64, bool)> {
+    let pointer_width = tcx.data_layout.pointer_size.bits();
+    match ty.kind() {
+        TyKind::Int(i) => Some((i.bit_width().unwrap_or(pointer_width), true)),
+        TyKind::Uint(u) => Some((u.bit_width().unwrap_or(pointer_width), false)),
+        _ => None,
+    }
+}
+
+/// `expr as ty`: truncates, sign-extends or reinterprets the sign as Rust does.
+pub(crate) fn cast<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    expr: PureExpression<'tcx>,
+    ty: Ty<'tcx>,
+) -> PureExpression<'tcx> {
+    let to = match Int::new(tcx, ty) {
+        Some(to) => to,
+        None => return expr.get_kind().with_ty(ty),
+    };
+    if expr.ty().is_bool() {
+        return ite(expr, to.lit(1), to.lit(0));
+    }
+    let from = match Int::new(tcx, expr.ty()) {
+        Some(from) => from,
+        None => return expr.get_kind().with_ty(ty),
+    };
+    let x = expr.get_kind().with_ty(ty);
+    let fits = to.bounds.0 <= from.bounds.0 && from.bounds.1 <= to.bounds.1;
+    if fits {
+        x
+    } else if from.bits <= to.bits && from.signed {
+        // Negative values wrap around
+        ite(
+            to.lt(x.clone(), to.lit(0)),
+            to.shift(x.clone(), BinOpKind::Add),
+            x,
+        )
+    } else if from.bits <= to.bits {
+        to.wrap_above(x)
+    } else {
+        to.truncate(x, from.signed)
+    }
+}
+
+/// `l op r` for the operators which do not map directly to SuSLik's: `/` and `%` on signed
+/// integers, and shifts by a literal.
+pub(crate) fn binary<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    op: BinOpKind,
+    l: PureExpression<'tcx>,
+    r: PureExpression<'tcx>,
+) -> Option<PureExpression<'tcx>> {
+    let int = Int::new(tcx, l.ty())?;
+    match op {
+        BinOpKind::Div | BinOpKind::Rem if int.signed => Some(int.div_rem(op, l, r)),
+        BinOpKind::Shl | BinOpKind::Shr => match r.kind() {
+            ExprKind::Lit(LitKind::Int(amount, _)) => int.shift_by(op, l, *amount),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// `!e` on an integer.
+pub(crate) fn not<'tcx>(tcx: TyCtxt<'tcx>, e: PureExpression<'tcx>) -> Option<PureExpression<'tcx>> {
+    Some(Int::new(tcx, e.ty())?.not(e))
+}
+
+/// Calls of integer methods with an exact spec, e.g. `a.wrapping_add(b)` or `a.min(b)`, as an
+/// expression over the `args`.
+pub(crate) fn inline_call<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    id: DefId,
+    substs: SubstsRef<'tcx>,
+    args: &[PureExpression<'tcx>],
+) -> Option<PureExpression<'tcx>> {
+    // Only the methods of `core`, not those of a trait which happens to have the same names
+    if tcx.crate_name(id.krate) != sym::core {
+        return None;
+    }
+    let is_ord = tcx
+        .trait_of_item(id)
+        .map_or(false, |tr| tcx.is_diagnostic_item(sym::Ord, tr));
+    let ty = if is_ord {
+        substs.types().next()?
+    } else {
+        tcx.type_of(tcx.impl_of_method(id)?)
+    };
+    Int::new(tcx, ty)?.op(tcx.item_name(id).as_str(), args)
+}
+
+/// The integer operations with built-in specs, for each of the integer types in `tys`, with the
+/// name of the type. Methods are called as such, the operators (with their first argument
+/// renamed from `self`) as e.g. `std::ops::Rem::rem(a, b)`.
+pub(crate) fn int_fns<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    tys: impl Iterator<Item = Ty<'tcx>>,
+) -> Vec<(String, RuslikFnSig<'tcx>)> {
+    let mut ints: Vec<_> = tys.filter_map(|ty| Int::new(tcx, ty)).collect();
+    ints.sort_by_key(|int| int.ty.to_string());
+    ints.dedup_by_key(|int| int.ty);
+    ints.into_iter()
+        .flat_map(|int| {
+            int.fns()
+                .into_iter()
+                .map(move |sig| (int.ty.to_string(), sig))
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy)]
+struct Int<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    ty: Ty<'tcx>,
+    bits: u64,
+    signed: bool,
+    /// The range of the type (see `Expr::int_bounds`)
+    bounds: (i128, u128),
+}
+
+impl<'tcx> Int<'tcx> {
+    fn new(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Self> {
+        let (bits, signed) = int_width(tcx, ty)?;
+        Some(Self {
+            tcx,
+            ty,
+            bits,
+            signed,
+            bounds: Expr::int_bounds(bits, signed),
+        })
+    }
+    /// `2^bits`, which is too large for a literal of a 128 bit integer
+    fn modulus(&self) -> PureExpression<'tcx> {
+        let max = self.lit(u128::MAX >> (128 - self.bits));
+        self.arith(BinOpKind::Add, max, self.lit(1))
+    }
+
+    fn lit(&self, value: u128) -> PureExpression<'tcx> {
+        PureExpression::from_u128(value, self.ty)
+    }
+    fn signed_lit(&self, value: i128) -> PureExpression<'tcx> {
+        let lit = self.lit(value.unsigned_abs());
+        if value < 0 {
+            -lit
+        } else {
+            lit
+        }
+    }
+    fn max(&self) -> PureExpression<'tcx> {
+        self.lit(self.bounds.1)
+    }
+    fn min(&self) -> PureExpression<'tcx> {
+        self.signed_lit(self.bounds.0)
+    }
+    fn arith(
+        &self,
+        op: BinOpKind,
+        l: PureExpression<'tcx>,
+        r: PureExpression<'tcx>,
+    ) -> PureExpression<'tcx> {
+        ExprKind::BinOp(op, Box::new(l), Box::new(r)).with_ty(self.ty)
+    }
+    fn cmp(
+        &self,
+        op: BinOpKind,
+        l: PureExpression<'tcx>,
+        r: PureExpression<'tcx>,
+    ) -> PureExpression<'tcx> {
+        ExprKind::BinOp(op, Box::new(l), Box::new(r)).with_ty(self.tcx.types.bool)
+    }
+    fn lt(&self, l: PureExpression<'tcx>, r: PureExpression<'tcx>) -> PureExpression<'tcx> {
+        self.cmp(BinOpKind::Lt, l, r)
+    }
+    fn le(&self, l: PureExpression<'tcx>, r: PureExpression<'tcx>) -> PureExpression<'tcx> {
+        self.cmp(BinOpKind::Le, l, r)
+    }
+    /// `e + 2^bits` (or `-` with `op`)
+    fn shift(&self, e: PureExpression<'tcx>, op: BinOpKind) -> PureExpression<'tcx> {
+        self.arith(op, e, self.modulus())
+    }
+    /// `e`, which is at most one modulus above the range, wrapped into it
+    fn wrap_above(&self, e: PureExpression<'tcx>) -> PureExpression<'tcx> {
+        let wrapped = self.shift(e.clone(), BinOpKind::Sub);
+        ite(self.lt(self.max(), e.clone()), wrapped, e)
+    }
+    /// `e`, which is at most one modulus outside of the range, wrapped into it
+    fn wrap(&self, e: PureExpression<'tcx>) -> PureExpression<'tcx> {
+        let below = ite(
+            self.lt(e.clone(), self.min()),
+            self.shift(e.clone(), BinOpKind::Add),
+            e.clone(),
+        );
+        ite(
+            self.lt(self.max(), e.clone()),
+            self.shift(e, BinOpKind::Sub),
+            below,
+        )
+    }
+    /// `e` (which may be negative if `signed`) truncated to the range, however far outside it is
+    fn truncate(&self, e: PureExpression<'tcx>, signed: bool) -> PureExpression<'tcx> {
+        // Truncate to `[0, modulus)`, then reinterpret the sign
+        let modulus = self.modulus();
+        let rem = |e: PureExpression<'tcx>| self.arith(BinOpKind::Rem, e, modulus.clone());
+        let truncated = if signed {
+            // `-1` is `modulus - 1`
+            let neg = self.arith(BinOpKind::Sub, -e.clone(), self.lit(1));
+            let umax = self.lit(u128::MAX >> (128 - self.bits));
+            let neg = self.arith(BinOpKind::Sub, umax, rem(neg));
+            ite(self.lt(e.clone(), self.lit(0)), neg, rem(e))
+        } else {
+            rem(e)
+        };
+        if self.signed {
+            self.wrap_above(truncated)
+        } else {
+            truncated
+        }
+    }
+    fn saturate(&self, e: PureExpression<'tcx>) -> PureExpression<'tcx> {
+        let below = ite(self.lt(e.clone(), self.min()), self.min(), e.clone());
+        ite(self.lt(self.max(), e), self.max(), below)
+    }
+    fn overflows(&self, e: PureExpression<'tcx>) -> PureExpression<'tcx> {
+        self.lt(self.max(), e.clone()) | self.lt(e, self.min())
+    }
+    /// `x / y` or `x % y`, truncating towards zero: the operation on the absolute values, negated
+    /// for a negative quotient or dividend respectively
+    fn div_rem(
+        &self,
+        op: BinOpKind,
+        x: PureExpression<'tcx>,
+        y: PureExpression<'tcx>,
+    ) -> PureExpression<'tcx> {
+        if !self.signed {
+            return self.arith(op, x, y);
+        }
+        let is_neg = |e: &PureExpression<'tcx>| self.lt(e.clone(), self.lit(0));
+        let abs = |e: &PureExpression<'tcx>| ite(is_neg(e), -e.clone(), e.clone());
+        let r = self.arith(op, abs(&x), abs(&y));
+        let neg_if = |cond| ite(cond, -r.clone(), r.clone());
+        match op {
+            BinOpKind::Div => ite(
+                is_neg(&x),
+                ite(is_neg(&y), r.clone(), -r.clone()),
+                neg_if(is_neg(&y)),
+            ),
+            _ => neg_if(is_neg(&x)),
+        }
+    }
+    /// `x << amount` or `x >> amount`, as a multiplication or division by `2^amount`
+    fn shift_by(
+        &self,
+        op: BinOpKind,
+        x: PureExpression<'tcx>,
+        amount: u128,
+    ) -> Option<PureExpression<'tcx>> {
+        if amount >= self.bits as u128 {
+            return None;
+        }
+        let factor = self.lit(1 << amount);
+        Some(match op {
+            BinOpKind::Shr => self.arith(BinOpKind::Div, x, factor),
+            _ => self.truncate(self.arith(BinOpKind::Mul, x, factor), self.signed),
+        })
+    }
+    /// `!x`: `-x - 1` for signed integers, `MAX - x` for unsigned ones
+    fn not(&self, x: PureExpression<'tcx>) -> PureExpression<'tcx> {
+        if self.signed {
+            self.arith(BinOpKind::Sub, -x, self.lit(1))
+        } else {
+            self.arith(BinOpKind::Sub, self.max(), x)
+        }
+    }
+
+    /// The methods with an exact spec
+    fn op(&self, name: &str, args: &[PureExpression<'tcx>]) -> Option<PureExpression<'tcx>> {
+        let arith = |op| self.arith(op, args[0].clone(), args[1].clone());
+        Some(match (name, args.len()) {
+            ("wrapping_add", 2) => self.wrap(arith(BinOpKind::Add)),
+            ("wrapping_sub", 2) => self.wrap(arith(BinOpKind::Sub)),
+            ("saturating_add", 2) => self.saturate(arith(BinOpKind::Add)),
+            ("saturating_sub", 2) => self.saturate(arith(BinOpKind::Sub)),
+            ("abs", 1) if self.signed => ite(
+                self.lt(args[0].clone(), self.lit(0)),
+                -args[0].clone(),
+                args[0].clone(),
+            ),
+            ("min", 2) => ite(
+                self.lt(args[1].clone(), args[0].clone()),
+                args[1].clone(),
+                args[0].clone(),
+            ),
+            ("max", 2) => ite(
+                self.lt(args[1].clone(), args[0].clone()),
+                args[0].clone(),
+                args[1].clone(),
+            ),
+            _ => return None,
+        })
+    }
+
+    fn fns(&self) -> Vec<RuslikFnSig<'tcx>> {
+        let tcx = self.tcx;
+        let mut fns = Vec::new();
+        let inherent = simplify_type(tcx, self.ty, TreatParams::AsPlaceholder)
+            .map_or(&[][..], |simple| tcx.incoherent_impls(simple));
+        let method = |name: &str| {
+            inherent.iter().find_map(|imp| {
+                tcx.associated_items(*imp)
+                    .filter_by_name_unhygienic(Symbol::intern(name))
+                    .next()
+                    .map(|item| item.def_id)
+            })
+        };
+        let mut methods = vec![
+            "wrapping_add",
+            "wrapping_sub",
+            "saturating_add",
+            "saturating_sub",
+        ];
+        if self.signed {
+            methods.push("abs");
+        }
+        for name in methods {
+            if let Some(id) = method(name) {
+                fns.push(self.sig(id, None, false, |args, result| {
+                    // `abs` overflows (and panics when debugging) on the minimum
+                    let pre = if name == "abs" {
+                        self.lt(self.min(), args[0].clone())
+                    } else {
+                        PureExpression::from_bool(true, tcx)
+                    };
+                    Some((pre, result._eq(self.op(name, args)?, tcx)))
+                }));
+            }
+        }
+        for (name, op) in [
+            ("checked_add", BinOpKind::Add),
+            ("checked_sub", BinOpKind::Sub),
+        ] {
+            if let Some(id) = method(name) {
+                fns.push(self.sig(id, None, false, |args, result| {
+                    let value = self.arith(op, args[0].clone(), args[1].clone());
+                    let (is_some, inner) = self.option(result, value.ty())?;
+                    let post = ite(
+                        is_some,
+                        inner._eq(value.clone(), tcx),
+                        self.overflows(value),
+                    );
+                    Some((PureExpression::from_bool(true, tcx), post))
+                }));
+            }
+        }
+        let ord = tcx.get_diagnostic_item(sym::Ord);
+        for name in ["min", "max"] {
+            let id = ord.and_then(|ord| {
+                tcx.associated_items(ord)
+                    .filter_by_name_unhygienic(Symbol::intern(name))
+                    .next()
+            });
+            if let Some(item) = id {
+                let substs = tcx.mk_substs_trait(self.ty, &[]);
+                fns.push(self.sig(item.def_id, Some(substs), false, |args, result| {
+                    Some((
+                        PureExpression::from_bool(true, tcx),
+                        result._eq(self.op(name, args)?, tcx),
+                    ))
+                }));
+            }
+        }
+        let lang_items = tcx.lang_items();
+        let operator = |item| {
+            let tr = lang_items.require(item).ok()?;
+            tcx.associated_items(tr)
+                .in_definition_order()
+                .find(|item| item.kind == AssocKind::Fn)
+                .map(|item| item.def_id)
+        };
+        let zero = || self.lit(0);
+        let mut ops = vec![
+            (LangItem::Div, BinOpKind::Div),
+            (LangItem::Rem, BinOpKind::Rem),
+        ];
+        if !self.signed {
+            ops.extend([
+                (LangItem::BitAnd, BinOpKind::BitAnd),
+                (LangItem::BitOr, BinOpKind::BitOr),
+                (LangItem::BitXor, BinOpKind::BitXor),
+            ]);
+        }
+        for (item, op) in ops {
+            let id = match operator(item) {
+                Some(id) => id,
+                None => continue,
+            };
+            let substs = tcx.mk_substs_trait(self.ty, &[self.ty.into()]);
+            fns.push(self.sig(id, Some(substs), true, |args, result| {
+                let (x, y) = (args[0].clone(), args[1].clone());
+                let le = |l, r| self.le(l, r);
+                Some(match op {
+                    // Dividing by zero, or the minimum by `-1`, panics
+                    BinOpKind::Div | BinOpKind::Rem => (
+                        self.cmp(BinOpKind::Ne, y.clone(), zero())
+                            & (self.cmp(BinOpKind::Ne, y.clone(), self.signed_lit(-1))
+                                | self.lt(self.min(), x.clone())),
+                        result._eq(self.div_rem(op, x, y), tcx),
+                    ),
+                    // Bit operations are not linear, only their bounds are known
+                    BinOpKind::BitAnd => (
+                        PureExpression::from_bool(true, tcx),
+                        le(result.clone(), x) & le(result, y),
+                    ),
+                    BinOpKind::BitOr => (
+                        PureExpression::from_bool(true, tcx),
+                        le(x.clone(), result.clone())
+                            & le(y.clone(), result.clone())
+                            & le(result, self.arith(BinOpKind::Add, x, y)),
+                    ),
+                    _ => (
+                        PureExpression::from_bool(true, tcx),
+                        le(result, self.arith(BinOpKind::Add, x, y)),
+                    ),
+                })
+            }));
+        }
+        if let Some(id) = operator(LangItem::Not) {
+            let substs = tcx.mk_substs_trait(self.ty, &[]);
+            fns.push(self.sig(id, Some(substs), true, |args, result| {
+                Some((
+                    PureExpression::from_bool(true, tcx),
+                    result._eq(self.not(args[0].clone()), tcx),
+                ))
+            }));
+        }
+        // Only the bounds of a shift right by a variable amount are known. A shift left can end
+        // up anywhere in the range, so it has no spec worth offering.
+        if let Some(id) = operator(LangItem::Shr) {
+            let u32 = tcx.types.u32;
+            let substs = tcx.mk_substs_trait(self.ty, &[u32.into()]);
+            fns.push(self.sig(id, Some(substs), true, |args, result| {
+                let (x, amount) = (args[0].clone(), args[1].clone());
+                // Shifting by the width or more panics when debugging
+                let pre = self.lt(amount, PureExpression::from_u128(self.bits as u128, u32));
+                let post = ite(
+                    self.lt(x.clone(), zero()),
+                    self.le(x.clone(), result.clone()) & self.lt(result.clone(), zero()),
+                    self.le(zero(), result.clone()) & self.le(result, x),
+                );
+                Some((pre, post))
+            }));
+        }
+        fns.into_iter().flatten().collect()
+    }
+
+    /// The signature of `id` (a trait method if there are `substs`) with the spec built by
+    /// `spec` from the arguments and result. The `self` of an `operator` is renamed, since its
+    /// trait is usually not in scope to call it as a method.
+    fn sig(
+        &self,
+        id: DefId,
+        substs: Option<SubstsRef<'tcx>>,
+        operator: bool,
+        spec: impl FnOnce(
+            &[PureExpression<'tcx>],
+            PureExpression<'tcx>,
+        ) -> Option<(PureExpression<'tcx>, PureExpression<'tcx>)>,
+    ) -> Option<RuslikFnSig<'tcx>> {
+        let tcx = self.tcx;
+        let t = PureExpression::from_bool(true, tcx);
+        let mut sig = RuslikFnSig::new(id, tcx, t.clone(), t, String::new(), 0);
+        if let Some(substs) = substs {
+            let subst = |ty| {
+                let ty = EarlyBinder(ty).subst(tcx, substs);
+                tcx.normalize_erasing_regions(ParamEnv::reveal_all(), ty)
+            };
+            for (_, ty) in &mut sig.args {
+                *ty = subst(*ty);
+            }
+            sig.ret = subst(sig.ret);
+        }
+        if operator {
+            sig.args[0].0 = Var::new("lhs");
+        }
+        let args: Vec<_> = sig
+            .args
+            .iter()
+            .map(|(arg, ty)| ExprKind::Var(*arg).with_ty(*ty))
+            .collect();
+        let result = ExprKind::Var(Var::new("result")).with_ty(sig.ret);
+        let (pre, post) = spec(&args, result)?;
+        sig.pure_pre = pre;
+        sig.pure_post = post;
+        Some(sig)
+    }
+
+    /// Whether `option` is `Some`, and the value of type `ty` in it
+    fn option(
+        &self,
+        option: PureExpression<'tcx>,
+        ty: Ty<'tcx>,
+    ) -> Option<(PureExpression<'tcx>, PureExpression<'tcx>)> {
+        let adt = match option.ty().kind() {
+            TyKind::Adt(adt, _) => *adt,
+            _ => return None,
+        };
+        let some = self.tcx.lang_items().require(LangItem::OptionSome).ok()?;
+        let some = adt.variant_index_with_id(some);
+        let disc = adt.discriminant_for_variant(self.tcx, some);
+        let inner = option.clone().field(some, Field::from_u32(0), ty);
+        Some((option.disc(disc, self.tcx), inner))
+    }
+}
+
+fn ite<'tcx>(
+    cond: PureExpression<'tcx>,
+    then: PureExpression<'tcx>,
+    els: PureExpression<'tcx>,
+) -> PureExpression<'tcx> {
+    let ty = then.ty();
+    ExprKind::IfElse(Box::new(cond), Box::new(then), Box::new(els)).with_ty(ty)
+}
@@ -1,14 +1,70 @@
 use rustc_data_structures::fx::FxHashMap;
 use rustc_hir::def_id::DefId;
-use rustc_middle::ty::TyCtxt;
+use rustc_middle::ty::{DefIdTree, TyCtxt};
+use rustc_span::Span;
 
 use crate::{
-    hir_translator::HirTranslator,
-    src_replace::replace_with_sln,
-    suslik::{SuslikProgram, SynthesisResult},
+    closure,
+    config::{RuslicConfig, SolutionStrategy},
+    crate_specs,
+    hir_translator::{HirTranslator, ImpureFn},
+    report::{Report, Target},
+    src_replace::SourceEdits,
+    std_specs,
+    suslik::{Solution, Solved, SuslikProgram, SynthesisResult, SynthesisResultKind, Vacuity},
+    suslik_jar::SuslikJar,
 };
 
-pub fn intern(tcx: TyCtxt, timeout: u64) -> Option<FxHashMap<String, SynthesisResult>> {
+/// With `export_specs` the specs of the crate are also written out for downstream crates (see
+/// `crate_specs.rs`).
+pub fn intern(
+    tcx: TyCtxt,
+    config: &RuslicConfig,
+    args: &[String],
+    export_specs: bool,
+) -> Option<FxHashMap<String, SynthesisResult>> {
+    let mut translator = translate(tcx)?;
+    if export_specs {
+        crate_specs::export(tcx, &translator);
+    }
+    crate_specs::import(tcx, &mut translator);
+    select_targets(tcx, config, &mut translator.impure_fns);
+    std_specs::retain(tcx, config, &mut translator.extern_fns);
+    if translator.impure_fns.is_empty() {
+        return Some(FxHashMap::default());
+    }
+    let suslik = match SuslikJar::find(config.suslik_jar.as_deref()) {
+        Ok(suslik) => suslik,
+        Err(err) => {
+            tcx.sess
+                .struct_err(&err.to_string())
+                .note("the jar is no longer built automatically")
+                .help("run `cargo russol setup` or point `SUSLIK_JAR` at an existing `suslik.jar`")
+                .emit();
+            return None;
+        }
+    };
+
+    let mut edits = SourceEdits::new(args);
+    let mut report = Report::default();
+    let times = if config.thread_count > 1 {
+        solve_multithreaded(tcx, config, &suslik, translator, &mut edits, &mut report)
+    } else {
+        solve(tcx, config, &suslik, translator, &mut edits, &mut report)
+    };
+    edits.apply(tcx, config);
+    report.write(tcx, config);
+    times
+}
+
+/// Only writes out the specs of the crate, for a dependency which isn't synthesized itself.
+pub fn export(tcx: TyCtxt) -> Option<()> {
+    let translator = translate(tcx)?;
+    crate_specs::export(tcx, &translator);
+    Some(())
+}
+
+fn translate(tcx: TyCtxt) -> Option<HirTranslator> {
     rustc_typeck::check_crate(tcx).ok()?;
     tcx.hir()
         .par_body_owners(|def_id| tcx.ensure().check_match(def_id.to_def_id()));
@@ -20,8 +76,14 @@ pub fn intern(tcx: TyCtxt, timeout: u64) -> Option<FxHashMap<String, SynthesisRe
     }
 
     let mut translator = HirTranslator::new(tcx);
-    for def_id in tcx.hir().body_owners() {
-        let def_id = def_id.to_def_id();
+    // Closures first: borrow checking the fn around them (as part of translating it) steals
+    // their THIR
+    let (closures, others): (Vec<_>, Vec<_>) = tcx
+        .hir()
+        .body_owners()
+        .map(|def_id| def_id.to_def_id())
+        .partition(|def_id| tcx.is_closure(*def_id));
+    for def_id in closures.into_iter().chain(others) {
         tcx.ensure().check_match(def_id);
         if tcx.sess.has_errors().is_some() {
             return None;
@@ -29,33 +91,79 @@ pub fn intern(tcx: TyCtxt, timeout: u64) -> Option<FxHashMap<String, SynthesisRe
         // println!("Translating {:?}", def_id);
         translator.translate(def_id);
     }
+    translator.inherit_trait_specs();
+    Some(translator)
+}
 
-    let multithreaded = std::env::var("RUSLIC_THREAD_COUNT")
-        .map(|v| v.parse::<usize>().unwrap())
-        .unwrap_or(8);
-    if multithreaded > 1 {
-        solve_multithreaded(tcx, timeout, translator, multithreaded)
-    } else {
-        solve(tcx, timeout, translator)
+/// Keeps only the fns which should be synthesized. These are holes (bodies which are only
+/// `ruslik!()`, `todo!()` or `unimplemented!()`) and fns which we were explicitly asked to
+/// synthesize: with `#[synth]`, `--only` or `--synth-bodies`. Fns with a real body are never
+/// overwritten otherwise. If any fn is `#[synth]` then only those are candidates, unless
+/// `--only` is given. Nested fns and closures of another target are dropped, since they are
+/// replaced along with its body.
+fn select_targets(tcx: TyCtxt, config: &RuslicConfig, impure_fns: &mut Vec<ImpureFn>) {
+    let only_synth = config.only.is_empty() && impure_fns.iter().any(|f| f.is_synth);
+    impure_fns.retain(|f| {
+        let name = tcx.def_path_str(f.sig.def_id);
+        let matches = |globs: &[String]| globs.iter().any(|g| glob_matches(g, &name));
+        let explicit = f.is_synth || !config.only.is_empty() || config.synth_bodies;
+        (!only_synth || f.is_synth)
+            && (f.is_hole || explicit)
+            && (!config.only_holes || f.is_hole)
+            && (config.only.is_empty() || matches(&config.only))
+            && !matches(&config.skip)
+    });
+    let targets: Vec<_> = impure_fns.iter().map(|f| f.sig.def_id).collect();
+    impure_fns.retain(|f| {
+        !targets
+            .iter()
+            .any(|&outer| outer != f.sig.def_id && tcx.is_descendant_of(f.sig.def_id, outer))
+    });
+}
+
+/// Matches `*` to any sequence of characters (including `::`) and `?` to any single character.
+pub(crate) fn glob_matches(glob: &str, text: &str) -> bool {
+    let (glob, text): (Vec<_>, Vec<_>) = (glob.chars().collect(), text.chars().collect());
+    // Indices after the last `*` seen in glob and the text position it was matched up to
+    let (mut g, mut t, mut star) = (0, 0, None);
+    while t < text.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g + 1, t));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                g += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Backtrack: let the last `*` consume one more character
+                Some((sg, st)) => {
+                    star = Some((sg, st + 1));
+                    g = sg;
+                    t = st + 1;
+                }
+                None => return false,
+            },
+        }
     }
+    glob[g..].iter().all(|&c| c == '*')
 }
 
 pub fn solve<'tcx>(
     tcx: TyCtxt<'tcx>,
-    timeout: u64,
+    config: &RuslicConfig,
+    suslik: &SuslikJar,
     translator: HirTranslator<'tcx>,
+    edits: &mut SourceEdits,
+    report: &mut Report,
 ) -> Option<FxHashMap<String, SynthesisResult>> {
-    let multifn = translator.impure_fns.len() > 1;
     let mut times = FxHashMap::default();
-    let only_synth = translator.impure_fns.iter().any(|(s, _)| *s);
-    for (synth, sig) in translator.impure_fns.into_iter() {
-        if only_synth && !synth {
-            continue;
-        }
-        let def_id = sig.def_id;
-        let name = tcx.def_path_str(def_id);
+    let targets = targets(tcx, &translator.impure_fns);
+    for (ImpureFn { sig, .. }, target) in translator.impure_fns.into_iter().zip(targets) {
         let result = SuslikProgram::solve(
             tcx,
+            config,
             sig,
             &translator.pure_fns,
             &translator
@@ -63,9 +171,10 @@ pub fn solve<'tcx>(
                 .iter()
                 .map(|ef| (*ef).clone())
                 .collect(),
-            timeout,
+            &translator.trait_specs,
+            suslik,
         )?;
-        handle_result(result, &mut times, tcx, def_id, name, multifn);
+        handle_result(tcx, config, target, result, &mut times, edits, report);
     }
 
     Some(times)
@@ -73,34 +182,26 @@ pub fn solve<'tcx>(
 
 pub fn solve_multithreaded<'tcx>(
     tcx: TyCtxt<'tcx>,
-    timeout: u64,
+    config: &RuslicConfig,
+    suslik: &SuslikJar,
     translator: HirTranslator<'tcx>,
-    thread_count: usize,
+    edits: &mut SourceEdits,
+    report: &mut Report,
 ) -> Option<FxHashMap<String, SynthesisResult>> {
-    let multifn = translator.impure_fns.len() > 1;
-    let only_synth = translator.impure_fns.iter().any(|(s, _)| *s);
-    let impure_fns = if only_synth {
-        translator
-            .impure_fns
-            .into_iter()
-            .filter(|(s, _)| *s)
-            .collect()
-    } else {
-        translator.impure_fns
-    };
-    let mut results: Vec<(DefId, Option<SynthesisResult>)> = Vec::new();
+    let targets = targets(tcx, &translator.impure_fns);
+    let mut results: Vec<(Target, Option<SynthesisResult>)> = Vec::new();
     let (tx, rx) = std::sync::mpsc::channel();
-    for (_, sig) in impure_fns.into_iter() {
-        results.push((sig.def_id, None));
-        if results.len() > thread_count {
-            let (idx, result) = rx.recv().unwrap();
-            let results: &mut (_, _) = &mut results[idx];
-            results.1 = result;
+    for (ImpureFn { sig, .. }, target) in translator.impure_fns.into_iter().zip(targets) {
+        results.push((target, None));
+        if results.len() > config.thread_count {
+            let (idx, result): (usize, _) = rx.recv().unwrap();
+            results[idx].1 = result;
         }
         SuslikProgram::solve_in_thread(
             tx.clone(),
             results.len() - 1,
             tcx,
+            config,
             sig,
             &translator.pure_fns,
             &translator
@@ -108,44 +209,124 @@ pub fn solve_multithreaded<'tcx>(
                 .iter()
                 .map(|ef| (*ef).clone())
                 .collect(),
-            timeout,
+            &translator.trait_specs,
+            suslik,
         );
     }
-    for _ in 0..std::cmp::min(results.len(), thread_count) {
+    for _ in 0..std::cmp::min(results.len(), config.thread_count) {
         let (idx, result) = rx.recv().unwrap();
         results[idx].1 = result;
     }
 
     let mut times = FxHashMap::default();
-    for (def_id, result) in results.into_iter() {
-        let name = tcx.def_path_str(def_id);
+    for (target, result) in results.into_iter() {
         let result = result.unwrap();
-        handle_result(result, &mut times, tcx, def_id, name, multifn);
+        handle_result(tcx, config, target, result, &mut times, edits, report);
     }
 
     Some(times)
 }
 
+/// Holes within a written body are named by their fn and their index in it, e.g.
+/// `List::push::{hole#0}`.
+fn targets(tcx: TyCtxt, impure_fns: &[ImpureFn]) -> Vec<Target> {
+    let mut holes: FxHashMap<DefId, usize> = FxHashMap::default();
+    impure_fns
+        .iter()
+        .map(|f| {
+            let def_id = f.sig.def_id;
+            let mut name = tcx.def_path_str(def_id);
+            if f.sketch.is_some() {
+                let idx = holes.entry(def_id).or_default();
+                name += &format!("::{{hole#{idx}}}");
+                *idx += 1;
+            }
+            Target {
+                def_id,
+                sketch: f.sketch,
+                name,
+                params: f.sig.params.clone(),
+            }
+        })
+        .collect()
+}
+
 pub fn handle_result(
-    result: SynthesisResult,
-    times: &mut FxHashMap<String, SynthesisResult>,
     tcx: TyCtxt,
-    def_id: DefId,
-    name: String,
-    multifn: bool,
+    config: &RuslicConfig,
+    target: Target,
+    mut result: SynthesisResult,
+    times: &mut FxHashMap<String, SynthesisResult>,
+    edits: &mut SourceEdits,
+    report: &mut Report,
 ) {
-    if let Some(sln) = result.get_solved() {
-        sln.print();
+    // eprintln!("Synth for {:?} result: {:?}", def_id, result);
+    if let Some(sln) = result.get_solved_mut() {
+        sln.print(config.print_sln_above);
+        // Choosing may type and borrow check the solutions, which is only needed to substitute one
+        if config.substitutes() {
+            let span = SourceEdits::target_span(tcx, target.def_id, target.sketch);
+            sln.chosen = choose_solution(config, tcx, &target, sln, span, edits);
+            match sln.chosen {
+                Some(idx) => edits.replace(tcx, span, solution_body(tcx, &target, &sln.slns[idx])),
+                None => tcx.sess.span_warn(
+                    span,
+                    &format!(
+                        "none of the {} solutions for `{}` type and borrow check, not substituting",
+                        sln.slns.len(),
+                        target.name
+                    ),
+                ),
+            }
+        }
     }
+    if let SynthesisResultKind::Vacuous(vacuity) = result.kind {
+        let problem = match vacuity {
+            Vacuity::Pre => "precondition is unsatisfiable",
+            Vacuity::Post => "postcondition is unsatisfiable under the precondition",
+        };
+        tcx.sess.span_warn(
+            tcx.def_span(target.def_id),
+            &format!("the {problem}, not synthesizing `{}`", target.name),
+        );
+    }
+    if let Some(explanation) = &result.explanation {
+        let span = target.sketch.unwrap_or_else(|| tcx.def_span(target.def_id));
+        tcx.sess.span_note_without_error(
+            span,
+            &format!("could not synthesize `{}`, {explanation}", target.name),
+        );
+    }
+    report.record(tcx, config, &target, &result);
+    times.insert(target.name, result);
+}
 
-    // eprintln!("Synth for {:?} result: {:?}", def_id, result);
-    let subst_result = std::env::var("RUSLIC_SUBST_RESULT")
-        .map(|v| v.parse::<bool>().unwrap())
-        .unwrap_or(false);
-    if let Some(sln) = result.get_solved() && subst_result {
-        let sln_lines = sln.slns[0].loc;
-        let sln = sln.slns[0].code.lines().skip(1).take(sln_lines).fold("\n".to_string(), |acc, line| acc + line + "\n");
-        replace_with_sln(tcx, def_id, sln, multifn);
+/// The body which replaces the target. A closure first binds the args which are references to
+/// its captured variables.
+fn solution_body(tcx: TyCtxt, target: &Target, sln: &Solution) -> String {
+    match target.sketch {
+        Some(_) => sln.body(),
+        None => closure::rebind_captures(tcx, target.def_id, sln.body()),
+    }
+}
+
+/// Pick one of the solutions by the `solution-strategy`.
+fn choose_solution(
+    config: &RuslicConfig,
+    tcx: TyCtxt,
+    target: &Target,
+    sln: &Solved,
+    span: Span,
+    edits: &SourceEdits,
+) -> Option<usize> {
+    let slns = sln.slns.iter().enumerate();
+    match config.solution_strategy {
+        SolutionStrategy::First => (!sln.slns.is_empty()).then_some(0),
+        SolutionStrategy::Smallest => slns.min_by_key(|(_, s)| s.loc).map(|(idx, _)| idx),
+        SolutionStrategy::FewestNodes => slns.min_by_key(|(_, s)| s.ast_nodes).map(|(idx, _)| idx),
+        SolutionStrategy::Checked => sln
+            .slns
+            .iter()
+            .position(|s| edits.check(tcx, span, solution_body(tcx, target, s))),
     }
-    times.insert(name, result);
 }
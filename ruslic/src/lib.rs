@@ -18,39 +18,68 @@ extern crate rustc_hir_pretty;
 extern crate rustc_infer;
 extern crate rustc_interface;
 extern crate rustc_middle;
+extern crate rustc_parse;
 extern crate rustc_session;
 extern crate rustc_span;
 extern crate rustc_target;
+extern crate rustc_trait_selection;
 extern crate rustc_type_ir;
 extern crate rustc_typeck;
 
+pub mod config;
+mod closure;
 mod constant;
 mod contract_translator;
+mod crate_specs;
 mod hir_translator;
+mod int_ops;
 mod interner;
+pub mod lsp_helpers;
+mod recheck;
+mod report;
 mod ruslik_pure;
 mod ruslik_pure_helpers;
 mod ruslik_ssl;
 mod ruslik_types;
+mod sketch;
 mod src_replace;
+mod std_specs;
 mod subst_generics;
 pub mod suslik;
+pub mod suslik_jar;
 mod suslik_normalize;
+pub mod suslik_trace;
 mod suslik_translate;
 mod trait_bounds;
+mod vacuity;
 // mod fnsig_regions;
 
 use rustc_data_structures::fx::FxHashMap;
 use rustc_driver::{Callbacks, Compilation, RunCompiler};
+use config::RuslicConfig;
 use rustc_interface::{interface::Compiler, Queries};
 use suslik::SynthesisResult;
 
 struct CompilerCallbacks {
+    args: Vec<String>,
     is_cargo: bool,
-    timeout: u64,
+    /// Don't synthesize, only export the specs of the crate (see `export_specs`)
+    export_only: bool,
+    config: RuslicConfig,
     timings: FxHashMap<String, SynthesisResult>,
 }
 impl Callbacks for CompilerCallbacks {
+    fn after_parsing<'tcx>(
+        &mut self,
+        compiler: &Compiler,
+        queries: &'tcx Queries<'tcx>,
+    ) -> Compilation {
+        if self.config.std_specs && !self.export_only {
+            let krate = &mut *queries.parse().unwrap().peek_mut();
+            crate::std_specs::inject(compiler.session(), krate);
+        }
+        Compilation::Continue
+    }
     fn after_expansion<'tcx>(
         &mut self,
         compiler: &Compiler,
@@ -76,11 +105,17 @@ impl Callbacks for CompilerCallbacks {
         queries.prepare_outputs().unwrap();
 
         queries.global_ctxt().unwrap().peek_mut().enter(|tcx| {
-            match crate::interner::intern(tcx, self.timeout) {
+            if self.export_only {
+                if crate::interner::export(tcx).is_none() {
+                    tcx.sess.abort_if_errors();
+                }
+                return;
+            }
+            match crate::interner::intern(tcx, &self.config, &self.args, self.is_cargo) {
                 Some(times) => self.timings = times,
                 None => {
+                    tcx.sess.abort_if_errors();
                     panic!("error");
-                    // tcx.sess.err(e.0);
                 }
             }
         });
@@ -100,23 +135,20 @@ fn sys_root() -> Vec<String> {
     vec!["--sysroot".into(), sysroot]
 }
 
-/// Pass rustc arguments in args (namely, path to rust file). Timeout is per function to be synthesized.
+/// Pass rustc arguments in args (namely, path to rust file).
 pub fn run_on_file(
     mut args: Vec<String>,
-    timeout: u64,
+    config: &RuslicConfig,
     is_cargo: bool,
 ) -> Result<FxHashMap<String, SynthesisResult>, rustc_errors::ErrorGuaranteed> {
-    let current_dir = std::env::current_exe().unwrap();
-    let current_dir = current_dir.parent().unwrap();
-    // Back out once more if running CI
-    let current_dir = if current_dir.ends_with("deps") {
-        current_dir.parent().unwrap()
-    } else {
-        current_dir
-    };
+    let current_dir = suslik_jar::exe_dir();
 
     if !is_cargo {
         args.push("--crate-type=lib".into());
+        // Under `cargo russol` these are truncated once for all crates, which then append to them
+        for path in config.emit_path.iter().chain(&config.report) {
+            std::fs::write(path, "").expect("could not create output file");
+        }
     }
     args.extend(["-A".into(), "dead_code".into()]);
     args.extend(["-A".into(), "unused_variables".into()]);
@@ -149,10 +181,26 @@ pub fn run_on_file(
 
     // println!("Running with args: {:?}", args);
     let mut cc = CompilerCallbacks {
+        args: args.clone(),
         is_cargo,
-        timeout,
+        export_only: false,
+        config: config.clone(),
         timings: FxHashMap::default(),
     };
     RunCompiler::new(&args, &mut cc).run()?;
     Ok(cc.timings)
 }
+
+/// Compiles a dependency under `cargo russol`, which isn't synthesized itself, writing out its
+/// specs so that they can be used by the crates depending on it.
+pub fn export_specs(mut args: Vec<String>) -> Result<(), rustc_errors::ErrorGuaranteed> {
+    args.extend(sys_root());
+    let mut cc = CompilerCallbacks {
+        args: args.clone(),
+        is_cargo: true,
+        export_only: true,
+        config: RuslicConfig::default(),
+        timings: FxHashMap::default(),
+    };
+    RunCompiler::new(&args, &mut cc).run()
+}
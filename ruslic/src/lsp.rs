@@ -0,0 +1,468 @@
+//! A minimal language server which offers a "Synthesize body" code action on fns with russol
+//! specs. Synthesis runs through `ruslic::run_on_file` (on the saved file, as `ruslic file.rs`
+//! would) with `emit = json`, and the solution is sent back to the editor as a `WorkspaceEdit`:
+//! the server never writes to the file itself.
+//!
+//! The saved file is compiled on its own as the crate root, so fns in a module of a multi-file
+//! crate are not supported: its `mod` declarations and `crate::` paths will not resolve.
+//!
+//! Options are taken from the nearest `Cargo.toml` and the environment as usual, and from
+//! `initializationOptions.args` (e.g. `["--timeout=60000"]`) in place of the command line.
+
+#![feature(rustc_private)]
+extern crate rustc_driver;
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use ruslic::{
+    config::{Emit, RuslicConfig},
+    lsp_helpers::{position, read_message, spec_fn_at, uri_to_path, SpecFn},
+    suslik::{SynthesisResult, SynthesisResultKind, Vacuity},
+};
+use serde_json::{json, Value};
+
+/// The command behind the code action, with arguments `[{ "uri", "line" }]`
+const SYNTHESIZE: &str = "russol.synthesize";
+/// How long to wait for the editor to answer a request sent by the server
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// Used unless the timeout is configured, the batch default is too long to wait for in an editor
+const DEFAULT_TIMEOUT: u64 = 60_000;
+
+// JSON-RPC error codes
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+const REQUEST_FAILED: i64 = -32803;
+
+// `MessageType`s of `window/showMessage`
+const ERROR: u8 = 1;
+const WARNING: u8 = 2;
+
+fn main() {
+    let server = Arc::new(Server::new(redirect_stdout()));
+    let mut input = BufReader::new(std::io::stdin());
+    let mut shutdown = false;
+    while let Some(msg) = read_message(&mut input) {
+        let method = match msg.get("method").and_then(Value::as_str) {
+            Some(method) => method,
+            // A response to a request of ours
+            None => {
+                server.response(msg);
+                continue;
+            }
+        };
+        let params = msg.get("params").cloned().unwrap_or(Value::Null);
+        match (method, msg.get("id").cloned()) {
+            ("exit", _) => std::process::exit(if shutdown { 0 } else { 1 }),
+            ("shutdown", Some(id)) => {
+                shutdown = true;
+                server.respond(id, Ok(Value::Null));
+            }
+            (_, Some(id)) => {
+                let result = server.handle_request(method, params);
+                server.respond(id, result);
+            }
+            (_, None) => server.handle_notification(method, params),
+        }
+    }
+}
+
+/// The synthesizer prints solutions (and more) to stdout, which the protocol needs for itself.
+/// Keep the real stdout for messages and send everything else to stderr (the server's log).
+#[cfg(unix)]
+fn redirect_stdout() -> File {
+    use std::os::unix::io::FromRawFd;
+    unsafe {
+        let out = libc::dup(1);
+        assert!(
+            out >= 0 && libc::dup2(2, 1) >= 0,
+            "could not redirect stdout"
+        );
+        File::from_raw_fd(out)
+    }
+}
+#[cfg(not(unix))]
+fn redirect_stdout() -> File {
+    eprintln!("error: `ruslic-lsp` is only supported on unix, where stdout can be redirected");
+    std::process::exit(1)
+}
+
+/// Clears the `busy` flag when synthesis is done, even if it panicked.
+struct Busy<'a>(&'a AtomicBool);
+impl Drop for Busy<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+struct Server {
+    out: Mutex<File>,
+    /// Requests sent to the editor which are waiting for a response, by id
+    pending: Mutex<HashMap<u64, mpsc::Sender<Value>>>,
+    next_id: AtomicU64,
+    /// The contents of open documents, by uri
+    docs: Mutex<HashMap<String, String>>,
+    /// Only one synthesis can run at a time, since the compiler session uses global state
+    busy: AtomicBool,
+    /// Whether the editor supports `window/workDoneProgress`
+    work_done_progress: AtomicBool,
+    /// Options from `initializationOptions.args`
+    args: Mutex<Vec<String>>,
+}
+
+#[derive(serde::Deserialize)]
+struct JsonEdit {
+    file: PathBuf,
+    range: Range<usize>,
+    replacement: String,
+}
+
+impl Server {
+    fn new(out: File) -> Self {
+        Self {
+            out: Mutex::new(out),
+            pending: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+            docs: Mutex::new(HashMap::new()),
+            busy: AtomicBool::new(false),
+            work_done_progress: AtomicBool::new(false),
+            args: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn handle_request(
+        self: &Arc<Self>,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, (i64, String)> {
+        match method {
+            "initialize" => {
+                let progress = &params["capabilities"]["window"]["workDoneProgress"];
+                self.work_done_progress
+                    .store(progress.as_bool().unwrap_or(false), Ordering::SeqCst);
+                if let Some(args) = params["initializationOptions"]["args"].as_array() {
+                    *self.args.lock().unwrap() = args
+                        .iter()
+                        .filter_map(|arg| Some(arg.as_str()?.to_string()))
+                        .collect();
+                }
+                Ok(json!({
+                    "capabilities": {
+                        // Full
+                        "textDocumentSync": 1,
+                        "codeActionProvider": true,
+                        "executeCommandProvider": { "commands": [SYNTHESIZE] },
+                    },
+                    "serverInfo": { "name": "ruslic-lsp", "version": env!("CARGO_PKG_VERSION") },
+                }))
+            }
+            "textDocument/codeAction" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let line = params["range"]["start"]["line"]
+                    .as_u64()
+                    .unwrap_or_default();
+                let docs = self.docs.lock().unwrap();
+                let spec_fn = docs
+                    .get(uri)
+                    .and_then(|text| spec_fn_at(text, line as usize));
+                Ok(match spec_fn {
+                    Some(SpecFn { name, line }) => {
+                        let title = format!("Synthesize body of `{name}`");
+                        let command = json!({
+                            "title": title,
+                            "command": SYNTHESIZE,
+                            "arguments": [{ "uri": uri, "line": line }],
+                        });
+                        json!([{ "title": title, "kind": "refactor.rewrite", "command": command }])
+                    }
+                    None => json!([]),
+                })
+            }
+            "workspace/executeCommand" => {
+                let args = &params["arguments"][0];
+                let (uri, line) = match (args["uri"].as_str(), args["line"].as_u64()) {
+                    (Some(uri), Some(line)) if params["command"] == SYNTHESIZE => {
+                        (uri.to_string(), line as usize)
+                    }
+                    _ => return Err((INVALID_PARAMS, format!("expected `{SYNTHESIZE}`"))),
+                };
+                if self.busy.swap(true, Ordering::SeqCst) {
+                    return Err((REQUEST_FAILED, "synthesis is already running".to_string()));
+                }
+                let server = self.clone();
+                std::thread::spawn(move || {
+                    let _busy = Busy(&server.busy);
+                    if let Err(err) = server.synthesize(&uri, line) {
+                        server.show_message(ERROR, &err);
+                    }
+                });
+                Ok(Value::Null)
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method `{method}`"))),
+        }
+    }
+
+    fn handle_notification(&self, method: &str, params: Value) {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let mut docs = self.docs.lock().unwrap();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                docs.insert(uri.to_string(), text.to_string());
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|c| c.last()?["text"].as_str()) {
+                    docs.insert(uri.to_string(), text.to_string());
+                }
+            }
+            "textDocument/didClose" => {
+                docs.remove(uri);
+            }
+            _ => (),
+        }
+    }
+
+    /// Synthesize the fn declared on `line` and send the solution to the editor. Errors are
+    /// shown to the user.
+    fn synthesize(&self, uri: &str, line: usize) -> Result<(), String> {
+        let path = uri_to_path(uri).ok_or_else(|| format!("`{uri}` is not a local file"))?;
+        let src = std::fs::read_to_string(&path)
+            .map_err(|e| format!("could not read `{}`: {e}", path.display()))?;
+        if self
+            .docs
+            .lock()
+            .unwrap()
+            .get(uri)
+            .map_or(false, |text| *text != src)
+        {
+            return Err("save the file before synthesizing".to_string());
+        }
+        let spec_fn = spec_fn_at(&src, line).ok_or("no fn with a russol spec here")?;
+        let fn_start: usize = src
+            .split_inclusive('\n')
+            .take(spec_fn.line)
+            .map(str::len)
+            .sum();
+
+        let emit_path =
+            std::env::temp_dir().join(format!("ruslic-lsp-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&emit_path);
+        let config = self.config(&path, spec_fn.name, emit_path.clone())?;
+        let progress = self.begin_progress(&format!("Synthesizing `{}`", spec_fn.name));
+        let (tx, rx) = mpsc::channel();
+        let args = vec!["ruslic".to_string(), path.to_string_lossy().into_owned()];
+        let timeout = config.timeout;
+        std::thread::spawn(move || {
+            let results =
+                std::panic::catch_unwind(|| ruslic::run_on_file(args, &config, false).ok());
+            let _ = tx.send(results.ok().flatten());
+        });
+        let start = Instant::now();
+        let results = loop {
+            match rx.recv_timeout(Duration::from_secs(1)) {
+                Ok(results) => break results,
+                Err(RecvTimeoutError::Timeout) => {
+                    let elapsed = start.elapsed().as_millis() as u64;
+                    let message = format!("{}s", elapsed / 1000);
+                    self.report_progress(&progress, &message, (100 * elapsed / timeout).min(99));
+                }
+                Err(RecvTimeoutError::Disconnected) => break None,
+            }
+        };
+        let edits = std::fs::read_to_string(&emit_path).unwrap_or_default();
+        let _ = std::fs::remove_file(&emit_path);
+        let results = match results {
+            Some(results) => results,
+            None => {
+                self.end_progress(&progress, "failed");
+                return Err(format!(
+                    "synthesis of `{}` failed, see the server's log",
+                    spec_fn.name
+                ));
+            }
+        };
+
+        // The body of this fn is the first edit after its declaration (later ones are
+        // other fns of the same name or holes within the body)
+        let edit = edits
+            .lines()
+            .filter_map(|line| serde_json::from_str::<JsonEdit>(line).ok())
+            .filter(|edit| edit.file == path && edit.range.start >= fn_start)
+            .min_by_key(|edit| edit.range.start);
+        let edit = match edit {
+            Some(edit) => edit,
+            None => {
+                self.end_progress(&progress, "no solution");
+                let outcome = results.iter().map(|(name, result)| outcome(name, result));
+                let message = outcome.collect::<Vec<_>>().join("; ");
+                return Err(if message.is_empty() {
+                    format!("`{}` was not synthesized", spec_fn.name)
+                } else {
+                    message
+                });
+            }
+        };
+        self.end_progress(&progress, "done");
+        let text_edit = json!({
+            "range": {
+                "start": position(&src, edit.range.start),
+                "end": position(&src, edit.range.end),
+            },
+            "newText": edit.replacement,
+        });
+        let label = format!("Synthesize body of `{}`", spec_fn.name);
+        let params = json!({ "label": label, "edit": { "changes": { uri: [text_edit] } } });
+        match self.request("workspace/applyEdit", params) {
+            Some(response) if response["result"]["applied"] == true => Ok(()),
+            Some(response) => {
+                let reason = response["result"]["failureReason"].as_str();
+                self.show_message(WARNING, reason.unwrap_or("the solution was not applied"));
+                Ok(())
+            }
+            None => Err("the editor did not apply the solution".to_string()),
+        }
+    }
+
+    /// The config for synthesizing only `name` in `path`, as json edits into `emit_path`.
+    fn config(&self, path: &Path, name: &str, emit_path: PathBuf) -> Result<RuslicConfig, String> {
+        let base = RuslicConfig {
+            timeout: DEFAULT_TIMEOUT,
+            // Report unsolvable fns rather than aborting
+            fail_on_unsynth: false,
+            ..Default::default()
+        };
+        let manifest = path
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join("Cargo.toml"))
+            .find(|manifest| manifest.is_file());
+        let args = self.args.lock().unwrap().clone();
+        let (mut config, rest) =
+            RuslicConfig::load(base, manifest.as_deref(), args).map_err(|e| e.to_string())?;
+        if !rest.is_empty() {
+            return Err(format!(
+                "unknown options in `initializationOptions.args`: {rest:?}"
+            ));
+        }
+        config.only = vec![name.to_string(), format!("*::{name}")];
+        config.emit = Emit::Json;
+        config.emit_path = Some(emit_path);
+        config.summarise = false;
+        config.summarise_json = false;
+        Ok(config)
+    }
+
+    fn begin_progress(&self, title: &str) -> Option<Value> {
+        if !self.work_done_progress.load(Ordering::SeqCst) {
+            return None;
+        }
+        let token = json!(format!(
+            "ruslic-{}",
+            self.next_id.fetch_add(1, Ordering::SeqCst)
+        ));
+        let created = self.request("window/workDoneProgress/create", json!({ "token": token }))?;
+        if created.get("error").is_some() {
+            return None;
+        }
+        let value =
+            json!({ "kind": "begin", "title": title, "cancellable": false, "percentage": 0 });
+        self.notify("$/progress", json!({ "token": token, "value": value }));
+        Some(token)
+    }
+    fn report_progress(&self, token: &Option<Value>, message: &str, percentage: u64) {
+        if let Some(token) = token {
+            let value = json!({ "kind": "report", "message": message, "percentage": percentage });
+            self.notify("$/progress", json!({ "token": token, "value": value }));
+        }
+    }
+    fn end_progress(&self, token: &Option<Value>, message: &str) {
+        if let Some(token) = token {
+            let value = json!({ "kind": "end", "message": message });
+            self.notify("$/progress", json!({ "token": token, "value": value }));
+        }
+    }
+
+    fn show_message(&self, kind: u8, message: &str) {
+        self.notify(
+            "window/showMessage",
+            json!({ "type": kind, "message": message }),
+        );
+    }
+
+    fn send(&self, mut msg: Value) {
+        msg["jsonrpc"] = json!("2.0");
+        let body = msg.to_string();
+        let mut out = self.out.lock().unwrap();
+        write!(out, "Content-Length: {}\r\n\r\n{body}", body.len())
+            .and_then(|()| out.flush())
+            .expect("could not write to stdout");
+    }
+    fn respond(&self, id: Value, result: Result<Value, (i64, String)>) {
+        self.send(match result {
+            Ok(result) => json!({ "id": id, "result": result }),
+            Err((code, message)) => {
+                json!({ "id": id, "error": { "code": code, "message": message } })
+            }
+        });
+    }
+    fn notify(&self, method: &str, params: Value) {
+        self.send(json!({ "method": method, "params": params }));
+    }
+    /// Send a request to the editor and wait for the response (which is `None` on timeout).
+    fn request(&self, method: &str, params: Value) -> Option<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = mpsc::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        self.send(json!({ "id": id, "method": method, "params": params }));
+        let response = rx.recv_timeout(REQUEST_TIMEOUT).ok();
+        self.pending.lock().unwrap().remove(&id);
+        response
+    }
+    fn response(&self, msg: Value) {
+        let pending = msg["id"]
+            .as_u64()
+            .and_then(|id| self.pending.lock().unwrap().remove(&id));
+        if let Some(tx) = pending {
+            let _ = tx.send(msg);
+        }
+    }
+}
+
+/// Why `name` was not substituted.
+fn outcome(name: &str, result: &SynthesisResult) -> String {
+    match &result.kind {
+        SynthesisResultKind::Unsupported(u) => match &u.ty {
+            Some(ty) => format!("`{name}` is unsupported ({:?}) due to `{ty}`", u.reason),
+            None => format!("`{name}` is unsupported ({:?})", u.reason),
+        },
+        SynthesisResultKind::Vacuous(Vacuity::Pre) => {
+            format!("the precondition of `{name}` is unsatisfiable")
+        }
+        SynthesisResultKind::Vacuous(Vacuity::Post) => {
+            format!("the postcondition of `{name}` contradicts its precondition")
+        }
+        SynthesisResultKind::Unsolvable(_) | SynthesisResultKind::Timeout => {
+            let failure = match result.kind {
+                SynthesisResultKind::Timeout => "synthesis timed out",
+                _ => "no solution exists",
+            };
+            match &result.explanation {
+                Some(explanation) => format!("{failure} for `{name}`, {explanation}"),
+                None => format!("{failure} for `{name}`"),
+            }
+        }
+        SynthesisResultKind::Solved(_) => format!("no solution for `{name}` could be substituted"),
+    }
+}
@@ -0,0 +1,99 @@
+//! The parts of `ruslic-lsp` which only look at text: the framing of messages, file uris,
+//! positions, and finding the fns which the "Synthesize body" action is offered on.
+
+use std::{io::BufRead, path::PathBuf};
+
+use serde_json::{json, Value};
+
+/// Attributes which mark a fn as having a russol spec
+pub const SPEC_ATTRS: [&str; 3] = ["#[requires", "#[ensures", "#[synth"];
+/// A fn which may be synthesized: its name and the line it is declared on.
+pub struct SpecFn<'a> {
+    pub name: &'a str,
+    pub line: usize,
+}
+
+/// Reads a message framed by its `Content-Length` header, `None` at the end of the input or if
+/// the message is malformed.
+pub fn read_message(input: &mut impl BufRead) -> Option<Value> {
+    let mut len = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).ok()? == 0 {
+            return None;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            len = value.trim().parse().ok();
+        }
+    }
+    let mut body = vec![0; len?];
+    input.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+/// The fn declared on or above `line`, if it has russol specs (or a `ruslik!()` body). This only
+/// looks at the text, so that offering the action never has to wait on the compiler.
+pub fn spec_fn_at(text: &str, line: usize) -> Option<SpecFn> {
+    let lines: Vec<_> = text.lines().collect();
+    let (line, name) = lines
+        .get(..=line)?
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(idx, l)| Some((idx, fn_name(l)?)))?;
+    // The attributes (and doc comments) directly above the fn
+    let mut attrs = lines[..line].iter().rev().take_while(|l| {
+        let l = l.trim_end();
+        !(l.trim().is_empty() || l.ends_with('}') || l.ends_with(';') || l.ends_with('{'))
+    });
+    let has_spec = attrs.any(|l| SPEC_ATTRS.iter().any(|attr| l.contains(attr)))
+        || lines[line..].iter().take(2).any(|l| l.contains("ruslik!("));
+    has_spec.then_some(SpecFn { name, line })
+}
+
+/// The name of the fn declared on `line`, if any.
+fn fn_name(line: &str) -> Option<&str> {
+    const QUALIFIERS: [&str; 6] = ["pub", "crate", "const", "async", "unsafe", "default"];
+    let mut words = line
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty());
+    let qualified = words
+        .by_ref()
+        .take_while(|&w| w != "fn")
+        .all(|w| QUALIFIERS.contains(&w));
+    if line.trim_start().starts_with("//") || !qualified {
+        return None;
+    }
+    words.next()
+}
+
+/// Only `file://` uris are supported.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    // Percent-decode
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(bytes).ok()?))
+}
+
+/// The LSP `Position` (with UTF-16 columns) of the byte `offset` into `text`.
+pub fn position(text: &str, offset: usize) -> Value {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[line_start..].encode_utf16().count(),
+    })
+}
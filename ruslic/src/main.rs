@@ -2,12 +2,23 @@
 extern crate rustc_driver;
 extern crate rustc_errors;
 
-use ruslic::suslik::{SynthesisResult, SynthesisResultKind};
+use std::path::PathBuf;
+
+use ruslic::{
+    config::RuslicConfig,
+    suslik::{SynthesisResult, SynthesisResultKind, Unsupported},
+};
 use rustc_errors::ErrorGuaranteed;
 
 fn main() -> Result<(), ErrorGuaranteed> {
     rustc_driver::catch_fatal_errors(|| {
         match filter_args() {
+            // Skip synth, but export the specs of dependencies which have any
+            (args, true, true) if uses_contracts(&args) => {
+                if ruslic::export_specs(args).is_err() {
+                    std::process::exit(1);
+                }
+            }
             // Skip synth
             (args, _, true) => {
                 let status = std::process::Command::new("rustc")
@@ -18,11 +29,12 @@ fn main() -> Result<(), ErrorGuaranteed> {
             }
             // Do synth
             (args, is_cargo, false) => {
-                let timeout = std::env::var("RUSLIC_TIMEOUT")
-                    .map(|v| v.parse::<u64>().unwrap())
-                    .unwrap_or(1_000_000);
-                if let Ok(res) = ruslic::run_on_file(args, timeout, is_cargo) {
-                    summarise(res.values().collect());
+                let (config, args) = load_config(args, is_cargo).unwrap_or_else(|e| {
+                    eprintln!("error: {e}");
+                    std::process::exit(1)
+                });
+                if let Ok(res) = ruslic::run_on_file(args, &config, is_cargo) {
+                    summarise(&config, res.values().collect());
                 }
             }
         }
@@ -50,17 +62,42 @@ fn filter_args() -> (Vec<String>, bool, bool) {
     (args, is_cargo, skip_synth)
 }
 
-fn summarise(res: Vec<&SynthesisResult>) {
-    if !std::env::var("RUSLIC_SUMMARISE")
-        .map(|v| v.parse().unwrap())
-        .unwrap_or(false)
-    {
+/// Whether the crate depends on `russol_contracts`, and so might have specs to export.
+fn uses_contracts(args: &[String]) -> bool {
+    args.iter().any(|arg| arg.starts_with("russol_contracts="))
+}
+
+/// When running under `cargo russol` the config has already been resolved. When used directly as
+/// a `RUSTC_WRAPPER` pick up `[package.metadata.russol]` of the crate being compiled, otherwise
+/// take flags from the command line (removing them from the args for rustc).
+fn load_config(
+    args: Vec<String>,
+    is_cargo: bool,
+) -> Result<(RuslicConfig, Vec<String>), ruslic::config::ConfigError> {
+    let manifest = std::env::var_os("CARGO_MANIFEST_DIR")
+        .filter(|_| is_cargo)
+        .map(|dir| PathBuf::from(dir).join("Cargo.toml"));
+    if let Some(config) = RuslicConfig::for_crate(manifest.as_deref()) {
+        return Ok((config?, args));
+    }
+    if is_cargo {
+        let (config, _) = RuslicConfig::load(RuslicConfig::default(), manifest.as_deref(), Vec::new())?;
+        Ok((config, args))
+    } else {
+        RuslicConfig::load(RuslicConfig::default(), None, args)
+    }
+}
+
+fn summarise(config: &RuslicConfig, res: Vec<&SynthesisResult>) {
+    if !config.summarise {
         return;
     }
-    let (mut unsupported, mut unsolvable, mut timeout, mut solved) = (0, 0, 0, Vec::new());
+    let (mut unsupported, mut vacuous, mut unsolvable, mut timeout, mut solved) =
+        (0, 0, 0, 0, Vec::new());
     for res in res.iter() {
         match &res.kind {
             SynthesisResultKind::Unsupported(_) => unsupported += 1,
+            SynthesisResultKind::Vacuous(_) => vacuous += 1,
             SynthesisResultKind::Unsolvable(_) => unsolvable += 1,
             SynthesisResultKind::Timeout => timeout += 1,
             SynthesisResultKind::Solved(s) => {
@@ -75,7 +112,7 @@ fn summarise(res: Vec<&SynthesisResult>) {
             }
         }
     }
-    println!("Unsupported: {unsupported}\nUnsolvable: {unsolvable}\nTimeout: {timeout}");
+    println!("Unsupported: {unsupported}\nVacuous: {vacuous}\nUnsolvable: {unsolvable}\nTimeout: {timeout}");
     print!("Solved: ");
     for (solved, lines, time) in solved.iter() {
         print!(" {solved} (loc {lines}, time {time}),");
@@ -85,10 +122,14 @@ fn summarise(res: Vec<&SynthesisResult>) {
     } else {
         println!();
     }
-    let json = std::env::var("RUSLIC_SUMMARISE_JSON")
-        .map(|v| v.parse::<bool>().unwrap())
-        .unwrap_or(false);
-    if json {
+    let top_tys = Unsupported::top_tys(res.iter().filter_map(|r| r.get_unsupported()), 20);
+    if !top_tys.is_empty() {
+        println!("Top unsupported types:");
+        for (ty, reason, count) in top_tys {
+            println!("  {count} `{ty}` ({reason:?})");
+        }
+    }
+    if config.summarise_json {
         let serialized = serde_json::to_string(&res).unwrap();
         assert!(!serialized.contains('\n'));
         println!("###### SUMMARY @@@@@@{serialized}");
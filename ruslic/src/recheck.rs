@@ -0,0 +1,56 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_driver::{Callbacks, Compilation, RunCompiler};
+use rustc_interface::{interface::Compiler, Config, Queries};
+use rustc_session::{
+    config::{OutputType, OutputTypes},
+    DiagnosticOutput,
+};
+use rustc_span::source_map::{FileLoader, RealFileLoader};
+
+/// Runs the compiler (with the same `args` as the current session) up to and including
+/// type and borrow checking, with the contents of some files replaced. Nothing is
+/// written to disk and diagnostics are discarded. Returns whether there were no errors.
+pub fn check_with(args: &[String], replaced: FxHashMap<PathBuf, String>) -> bool {
+    let mut callbacks = Recheck {
+        replaced: Some(replaced),
+    };
+    let result = rustc_driver::catch_fatal_errors(|| RunCompiler::new(args, &mut callbacks).run());
+    matches!(result, Ok(Ok(())))
+}
+
+struct Recheck {
+    replaced: Option<FxHashMap<PathBuf, String>>,
+}
+impl Callbacks for Recheck {
+    fn config(&mut self, config: &mut Config) {
+        config.diagnostic_output = DiagnosticOutput::Raw(Box::new(io::sink()));
+        config.file_loader = Some(Box::new(ReplacedFiles(self.replaced.take().unwrap())));
+        config.opts.output_types = OutputTypes::new(&[(OutputType::Metadata, None)]);
+        config.opts.incremental = None;
+    }
+    fn after_analysis<'tcx>(
+        &mut self,
+        _compiler: &Compiler,
+        _queries: &'tcx Queries<'tcx>,
+    ) -> Compilation {
+        Compilation::Stop
+    }
+}
+
+struct ReplacedFiles(FxHashMap<PathBuf, String>);
+impl FileLoader for ReplacedFiles {
+    fn file_exists(&self, path: &Path) -> bool {
+        self.0.contains_key(path) || RealFileLoader.file_exists(path)
+    }
+    fn read_file(&self, path: &Path) -> io::Result<String> {
+        match self.0.get(path) {
+            Some(src) => Ok(src.clone()),
+            None => RealFileLoader.read_file(path),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::io::Write;
+
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+use crate::{
+    config::RuslicConfig,
+    suslik::{Reason, SynthesisResult, SynthesisResultKind, Vacuity},
+    suslik_trace::Explanation,
+};
+
+/// A fn, or a `ruslik!()` hole within one, which is synthesized.
+pub struct Target {
+    pub def_id: DefId,
+    /// The span of the hole, if this is a hole within a written body
+    pub sketch: Option<Span>,
+    /// The def path, with `::{hole#i}` appended for holes
+    pub name: String,
+    /// The `#[params]` given to SuSLik
+    pub params: String,
+}
+
+/// The `report` file, with one json record per `Target` in the order they were synthesized.
+#[derive(Default)]
+pub struct Report {
+    lines: String,
+}
+
+#[derive(serde::Serialize)]
+struct Record<'a> {
+    name: &'a str,
+    def_path: String,
+    file: String,
+    /// First and last line (1-based) of the fn or hole
+    lines: (usize, usize),
+    outcome: &'static str,
+    reason: Option<Reason>,
+    /// For `unsupported`, the offending type and how it is reached from the signature
+    unsupported_ty: Option<&'a str>,
+    unsupported_path: Option<&'a str>,
+    is_trivial: bool,
+    /// How long SuSLik ran for (until the timeout if it timed out)
+    time_ms: Option<u64>,
+    solutions: usize,
+    chosen: Option<Chosen>,
+    /// With `output-trace`, why SuSLik failed
+    explanation: Option<&'a Explanation>,
+    params: &'a str,
+}
+
+#[derive(serde::Serialize)]
+struct Chosen {
+    idx: usize,
+    loc: usize,
+    ast_nodes: u64,
+    synth_time_ms: u64,
+    /// The body of the chosen solution
+    body: String,
+}
+
+impl Report {
+    pub fn record(
+        &mut self,
+        tcx: TyCtxt,
+        config: &RuslicConfig,
+        target: &Target,
+        result: &SynthesisResult,
+    ) {
+        if config.report.is_none() {
+            return;
+        }
+        let span = target
+            .sketch
+            .unwrap_or_else(|| tcx.source_span(target.def_id.expect_local()));
+        let sm = tcx.sess.source_map();
+        let (lo, hi) = (sm.lookup_char_pos(span.lo()), sm.lookup_char_pos(span.hi()));
+        let (outcome, time_ms) = match &result.kind {
+            SynthesisResultKind::Unsupported(_) => ("unsupported", None),
+            SynthesisResultKind::Vacuous(Vacuity::Pre) => ("vacuous-pre", None),
+            SynthesisResultKind::Vacuous(Vacuity::Post) => ("vacuous-post", None),
+            SynthesisResultKind::Unsolvable(time) => ("unsolvable", Some(*time)),
+            SynthesisResultKind::Timeout => ("timeout", Some(config.timeout)),
+            SynthesisResultKind::Solved(sln) => ("solved", Some(sln.exec_time)),
+        };
+        let solved = result.get_solved();
+        let unsupported = result.get_unsupported();
+        let record = Record {
+            name: &target.name,
+            def_path: tcx.def_path_str(target.def_id),
+            file: lo.file.name.prefer_local().to_string(),
+            lines: (lo.line, hi.line),
+            outcome,
+            reason: unsupported.map(|u| u.reason),
+            unsupported_ty: unsupported.and_then(|u| u.ty.as_deref()),
+            unsupported_path: unsupported.and_then(|u| u.path.as_deref()),
+            is_trivial: result.is_trivial,
+            time_ms,
+            solutions: solved.map_or(0, |sln| sln.slns.len()),
+            chosen: solved.and_then(|sln| {
+                let idx = sln.chosen?;
+                let chosen = &sln.slns[idx];
+                Some(Chosen {
+                    idx,
+                    loc: chosen.loc,
+                    ast_nodes: chosen.ast_nodes,
+                    synth_time_ms: chosen.synth_time,
+                    body: chosen.body(),
+                })
+            }),
+            explanation: result.explanation.as_ref(),
+            params: &target.params,
+        };
+        self.lines += &serde_json::to_string(&record).unwrap();
+        self.lines.push('\n');
+    }
+
+    /// Append all records to the `report` file at once.
+    pub fn write(self, tcx: TyCtxt, config: &RuslicConfig) {
+        if let Some(path) = &config.report {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut f| f.write_all(self.lines.as_bytes()))
+                .unwrap_or_else(|e| {
+                    tcx.sess.err(&format!(
+                        "failed to write report to `{}`: {e}",
+                        path.display()
+                    ));
+                });
+        }
+    }
+}
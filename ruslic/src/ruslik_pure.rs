@@ -28,6 +28,14 @@ pub enum CallInfo<'tcx> {
 pub enum BuiltinCallKind {
     SetConstruct,
     SetContains,
+    Len,
+    VecElems,
+    VecIndex,
+    SeqConstruct,
+    SeqTake,
+    SeqSkip,
+    StrEq,
+    CellGet,
 }
 type BinOp = rustc_hir::BinOpKind;
 type Lit = rustc_ast::ast::LitKind;
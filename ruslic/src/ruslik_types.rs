@@ -54,6 +54,23 @@ impl<'tcx> RuslikFnSig<'tcx> {
         params: String,
         ast_nodes: usize,
     ) -> Self {
+        let outlives = Rc::new(OutlivesEnvironment::new(tcx.param_env(def_id)));
+        if crate::closure::is_closure(tcx, def_id) {
+            let (args, ret) = crate::closure::closure_sig(tcx, def_id);
+            let (mut pure_pre, mut pure_post) = (pure_pre, pure_post);
+            crate::closure::deref_captures(tcx, def_id, &mut pure_pre);
+            crate::closure::deref_captures(tcx, def_id, &mut pure_post);
+            return RuslikFnSig {
+                args,
+                ret,
+                pure_pre,
+                pure_post,
+                def_id,
+                outlives,
+                params,
+                ast_nodes,
+            };
+        }
         // println!("{:?}", self.tcx.named_region_map(def_id.expect_local()));
         // println!("{:?}", def_id.expect_local());
         if let Some(local) = def_id.as_local() {
@@ -65,7 +82,6 @@ impl<'tcx> RuslikFnSig<'tcx> {
         let gen_sig = tcx.fn_sig(def_id);
         // println!("{:?}", gen_sig.inputs_and_output());
 
-        let outlives = Rc::new(OutlivesEnvironment::new(tcx.param_env(def_id)));
         // println!("{:?}", outlives.free_region_map());
         // let sig = self
         //     .tcx
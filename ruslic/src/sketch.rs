@@ -0,0 +1,643 @@
+use rustc_ast::Mutability;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::BinOpKind;
+use rustc_middle::{
+    mir::{BorrowKind, UnOp},
+    thir::{
+        visit::{walk_expr, Visitor},
+        BindingMode, Expr, ExprId, ExprKind, Guard, Pat, PatKind, StmtKind, Thir,
+    },
+    ty::{ParamEnv, Ty, TyCtxt, TypeVisitable, WithOptConstParam},
+};
+use rustc_span::{
+    hygiene::{ExpnKind, MacroKind},
+    Span, Symbol,
+};
+
+use crate::{
+    contract_translator::{pattern_to_expr, thir_expr_to_expr, VarMap},
+    ruslik_pure::{ExprKind as PExprKind, PureExpression, UnOpKind},
+    ruslik_pure_helpers::PureExpressionWalker,
+    ruslik_ssl::Var,
+    ruslik_types::{AdtIdent, RuslikFnSig},
+};
+
+/// The outermost bang macro which `span` was expanded from, and the span of its invocation.
+/// Not using `macro_backtrace` since that skips e.g. `ruslik!` (its call site is the same as
+/// that of the `panic!` it expands to).
+pub(crate) fn outer_macro_call(mut span: Span) -> Option<(Symbol, Span)> {
+    let mut outer_macro = None;
+    loop {
+        let expn = span.ctxt().outer_expn_data();
+        if expn.is_root() {
+            break;
+        }
+        span = expn.call_site;
+        outer_macro = match expn.kind {
+            ExpnKind::Macro(MacroKind::Bang, name) => Some((name, span)),
+            _ => None,
+        };
+    }
+    outer_macro
+}
+
+fn is_ruslik_call(span: Span) -> Option<Span> {
+    outer_macro_call(span)
+        .filter(|(name, _)| name.as_str() == "ruslik")
+        .map(|(_, span)| span)
+}
+
+/// A `ruslik!()` hole within an otherwise written fn body. The `sig` has the variables in
+/// scope at the hole as arguments and the type of the hole as the return type.
+pub struct Sketch<'tcx> {
+    /// The span of the `ruslik!()` invocation, to be replaced by the solution
+    pub span: Span,
+    pub sig: RuslikFnSig<'tcx>,
+}
+
+/// The synthesis context at some point in a fn body.
+#[derive(Clone)]
+struct Context<'tcx> {
+    /// Variables in scope which the hole may use
+    args: Vec<(Var, Ty<'tcx>)>,
+    /// Variables which were moved out of by a match, with what they are equal to in terms of
+    /// the bindings (if the pattern was irrefutable enough to reconstruct them)
+    moved: FxHashMap<Var, Option<PureExpression<'tcx>>>,
+    /// Facts which hold at this point (the precondition and path conditions)
+    facts: Vec<PureExpression<'tcx>>,
+    /// Bindings which are not in `args` since they only view a variable which is still in
+    /// scope, with what they are bound to
+    views: VarMap<'tcx>,
+    /// If the context cannot be expressed, why
+    unsupported: Option<&'static str>,
+}
+
+/// Finds `ruslik!()` holes in the body of `def_id` and computes the context at each one.
+/// Only holes in the result position of the body are supported (reached through blocks,
+/// `if`s and `match` arms), since for those the postcondition of the fn is known.
+/// Only `let` statements may come before the hole, and neither they nor the conditions on
+/// the way to it may move out of, mutably borrow or assign to variables (see `Effects`),
+/// other than match scrutinees which are replaced by the bindings of the arm.
+pub struct SketchCollector<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    thir: &'a Thir<'tcx>,
+    sig: &'a RuslikFnSig<'tcx>,
+    /// The statements at the start of the body which hold the specs (see `ruslik_spec_count`)
+    spec_stmts: usize,
+    /// The holes which were reached, even if they could not be synthesized
+    reached: Vec<Span>,
+    pub sketches: Vec<Sketch<'tcx>>,
+}
+
+impl<'a, 'tcx> SketchCollector<'a, 'tcx> {
+    pub fn collect(
+        tcx: TyCtxt<'tcx>,
+        sig: &RuslikFnSig<'tcx>,
+        spec_stmts: usize,
+    ) -> Vec<Sketch<'tcx>> {
+        let def_id = sig.def_id.expect_local();
+        let (thir, body) = tcx.thir_body(WithOptConstParam::unknown(def_id)).unwrap();
+        let thir = &thir.borrow();
+        let holes: Vec<_> = thir
+            .exprs
+            .iter()
+            .filter_map(|e| is_ruslik_call(e.span))
+            .fold(Vec::new(), |mut acc, span| {
+                if !acc.contains(&span) {
+                    acc.push(span);
+                }
+                acc
+            });
+        if holes.is_empty() {
+            return Vec::new();
+        }
+        let mut sc = SketchCollector {
+            tcx,
+            thir,
+            sig,
+            spec_stmts,
+            reached: Vec::new(),
+            sketches: Vec::new(),
+        };
+        let ctx = Context {
+            args: sig.args.clone(),
+            moved: FxHashMap::default(),
+            facts: conjuncts(sig.pure_pre.clone()),
+            views: VarMap::default(),
+            unsupported: None,
+        };
+        sc.walk(body, ctx);
+        for hole in holes {
+            if !sc.reached.contains(&hole) {
+                tcx.sess.span_warn(
+                    hole,
+                    "`ruslik!()` hole is not in the result position of the fn, it will not be synthesized",
+                );
+            }
+        }
+        sc.sketches
+    }
+
+    fn walk(&mut self, expr: ExprId, mut ctx: Context<'tcx>) {
+        let e = &self.thir[expr];
+        if let Some(span) = is_ruslik_call(e.span) {
+            self.hole(span, e.ty, ctx);
+            return;
+        }
+        match e.kind {
+            ExprKind::Scope { value: inner, .. }
+            | ExprKind::NeverToAny { source: inner }
+            | ExprKind::Use { source: inner } => self.walk(inner, ctx),
+            ExprKind::Block { block } => {
+                let block = &self.thir[block];
+                // The body is the first block reached
+                let specs = std::mem::take(&mut self.spec_stmts);
+                for stmt in block.stmts.iter().skip(specs) {
+                    match self.thir[*stmt].kind {
+                        StmtKind::Let {
+                            ref pattern,
+                            initializer,
+                            ..
+                        } => {
+                            match initializer {
+                                // `let y = x;` moves (or copies) `x` into `y`
+                                Some(init) if matches!(pattern.kind, PatKind::Binding { .. }) => {
+                                    self.check_effects(&mut ctx, init)
+                                }
+                                Some(init) => self.check_scrutinee(&mut ctx, init),
+                                None => (),
+                            }
+                            self.bind(&mut ctx, pattern, initializer);
+                        }
+                        StmtKind::Expr { .. } => {
+                            ctx.unsupported = Some("statement other than `let`")
+                        }
+                    }
+                }
+                if let Some(e) = block.expr {
+                    self.walk(e, ctx)
+                }
+            }
+            ExprKind::If {
+                cond, then, else_opt, ..
+            } => {
+                let mut then_ctx = ctx.clone();
+                match self.thir[cond].kind {
+                    ExprKind::Let { expr, ref pat } => {
+                        self.check_scrutinee(&mut then_ctx, expr);
+                        self.bind(&mut then_ctx, pat, Some(expr))
+                    }
+                    _ => {
+                        self.check_effects(&mut then_ctx, cond);
+                        self.check_effects(&mut ctx, cond);
+                        if let Some(cond) = self.try_translate(cond, &ctx.views) {
+                            then_ctx.facts.push(cond.clone());
+                            let ty = cond.ty();
+                            ctx.facts
+                                .push(PExprKind::UnOp(UnOpKind::UnOp(UnOp::Not), Box::new(cond)).with_ty(ty));
+                        }
+                    }
+                }
+                self.walk(then, then_ctx);
+                if let Some(els) = else_opt {
+                    self.walk(els, ctx);
+                }
+            }
+            ExprKind::Match {
+                scrutinee,
+                ref arms,
+            } => {
+                for arm in arms.iter() {
+                    let arm = &self.thir[*arm];
+                    let mut arm_ctx = ctx.clone();
+                    self.check_scrutinee(&mut arm_ctx, scrutinee);
+                    self.bind(&mut arm_ctx, &arm.pattern, Some(scrutinee));
+                    match arm.guard {
+                        Some(Guard::If(guard)) => {
+                            self.check_effects(&mut arm_ctx, guard);
+                            if let Some(guard) = self.try_translate(guard, &arm_ctx.views) {
+                                arm_ctx.facts.push(guard);
+                            }
+                        }
+                        Some(Guard::IfLet(ref pat, expr)) => {
+                            self.check_scrutinee(&mut arm_ctx, expr);
+                            self.bind(&mut arm_ctx, pat, Some(expr))
+                        }
+                        None => (),
+                    }
+                    self.walk(arm.body, arm_ctx);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Update the context with the bindings of `pat` matched against `init`.
+    fn bind(&self, ctx: &mut Context<'tcx>, pat: &Pat<'tcx>, init: Option<ExprId>) {
+        let mut bindings = Vec::new();
+        collect_bindings(pat, &mut bindings);
+        let scrutinee = init.map(|init| self.descope(init));
+        let root = scrutinee.and_then(|s| self.try_translate(s, &ctx.views));
+        let var_map = match root {
+            Some(root) => {
+                let (cond, var_map) = pattern_to_expr(
+                    self.tcx,
+                    self.sig.def_id.expect_local(),
+                    self.thir,
+                    pat,
+                    root,
+                );
+                ctx.facts.push(cond);
+                var_map
+            }
+            None => FxHashMap::default(),
+        };
+        let param_env = self.tcx.param_env(self.sig.def_id);
+        let moves = bindings.iter().any(|b| {
+            b.mode == BindingMode::ByValue
+                && !b.ty.is_copy_modulo_regions(self.tcx.at(pat.span), param_env)
+        });
+        let is_match = init.is_some() && !matches!(pat.kind, PatKind::Binding { .. });
+        if moves && is_match {
+            // Moving out of the scrutinee, it is replaced by the bindings
+            match scrutinee.map(|s| &self.thir[s].kind) {
+                Some(ExprKind::VarRef { id }) => {
+                    let var = Var::arg(self.tcx.hir().name(id.0));
+                    ctx.args.retain(|(v, _)| *v != var);
+                    ctx.moved.insert(var, self.reconstruct(pat));
+                }
+                _ => ctx.unsupported = Some("moving out of a match scrutinee which is not a variable"),
+            }
+        }
+        for b in bindings {
+            // Bindings by reference are only views of the scrutinee, which stays in scope
+            if is_match && (!moves || b.mode != BindingMode::ByValue) {
+                if let Some(value) = var_map.get(&b.hir_id) {
+                    ctx.views.insert(b.hir_id, value.clone());
+                }
+                continue;
+            }
+            if b.ty.has_erased_regions() {
+                ctx.unsupported = Some("a binding with an inferred lifetime");
+            }
+            let var = Var::arg(b.name);
+            // The hole can only refer to the new variable by its name, while the facts, the
+            // reconstructions of moved variables and the postcondition refer to the old one
+            let shadows = |(v, _): &(Var, _)| *v == var;
+            if ctx.args.iter().any(shadows) || self.sig.args.iter().any(shadows) || ctx.moved.contains_key(&var) {
+                ctx.unsupported = Some("binding which shadows a variable");
+            }
+            ctx.args.retain(|(v, _)| *v != var);
+            ctx.args.push((var, b.ty));
+            ctx.moved.remove(&var);
+            if let Some(value) = var_map.get(&b.hir_id) && !moves {
+                let binding = PExprKind::Var(var).with_ty(b.ty);
+                ctx.facts.push(binding._eq(value.clone(), self.tcx));
+            }
+        }
+    }
+
+    /// The value matched by `pat`, in terms of its bindings.
+    fn reconstruct(&self, pat: &Pat<'tcx>) -> Option<PureExpression<'tcx>> {
+        match &pat.kind {
+            PatKind::Binding {
+                name,
+                mode: BindingMode::ByValue,
+                subpattern: None,
+                ..
+            } => Some(PExprKind::Var(Var::arg(*name)).with_ty(pat.ty)),
+            PatKind::Variant {
+                adt_def,
+                variant_index,
+                subpatterns,
+                ..
+            } if subpatterns.len() == adt_def.variant(*variant_index).fields.len() => {
+                let fields = subpatterns
+                    .iter()
+                    .map(|fp| Some((fp.field, self.reconstruct(&fp.pattern)?)))
+                    .collect::<Option<_>>()?;
+                Some(
+                    PExprKind::Constructor(self.tcx.item_name(adt_def.did()), *variant_index, fields)
+                        .with_ty(pat.ty),
+                )
+            }
+            PatKind::Leaf { subpatterns } => {
+                let fields = subpatterns
+                    .iter()
+                    .map(|fp| Some((fp.field, self.reconstruct(&fp.pattern)?)))
+                    .collect::<Option<Vec<_>>>()?;
+                let adt = match pat.ty.kind() {
+                    rustc_middle::ty::TyKind::Adt(adt_def, _)
+                        if fields.len() == adt_def.non_enum_variant().fields.len() =>
+                    {
+                        self.tcx.item_name(adt_def.did())
+                    }
+                    rustc_middle::ty::TyKind::Tuple(tys) if fields.len() == tys.len() => {
+                        AdtIdent::intern(&format!("tuple_{}", fields.len()))
+                    }
+                    _ => return None,
+                };
+                Some(PExprKind::Constructor(adt, 0_u32.into(), fields).with_ty(pat.ty))
+            }
+            _ => None,
+        }
+    }
+
+    fn hole(&mut self, span: Span, ty: Ty<'tcx>, ctx: Context<'tcx>) {
+        self.reached.push(span);
+        if let Some(reason) = ctx.unsupported {
+            self.tcx.sess.span_warn(
+                span,
+                &format!("cannot synthesize `ruslik!()` hole: unsupported {reason} on the way to it"),
+            );
+            return;
+        }
+        if ty.has_erased_regions() {
+            self.tcx.sess.span_warn(
+                span,
+                "cannot synthesize `ruslik!()` hole with an inferred lifetime in its type",
+            );
+            return;
+        }
+        let mut subst = Substitute {
+            moved: &ctx.moved,
+            failed: false,
+        };
+        // Facts which cannot be expressed after moving can be dropped, that only weakens the precondition
+        let pre = ctx
+            .facts
+            .into_iter()
+            .filter_map(|mut f| subst.apply(&mut f).then_some(f))
+            .fold(PureExpression::from_bool(true, self.tcx), |acc, f| acc & f);
+        // The postcondition must be kept as is
+        let mut post = self.sig.pure_post.clone();
+        if !subst.apply(&mut post) {
+            self.tcx.sess.span_warn(
+                span,
+                "cannot synthesize `ruslik!()` hole: the postcondition mentions a variable which was moved out of by a refutable pattern",
+            );
+            return;
+        }
+        let sig = RuslikFnSig {
+            args: ctx.args,
+            ret: ty,
+            pure_pre: pre,
+            pure_post: post,
+            ..self.sig.clone()
+        };
+        self.sketches.push(Sketch { span, sig });
+    }
+
+    /// Marks the context as unsupported if evaluating `expr` has effects on the variables in
+    /// scope which the context cannot express.
+    fn check_effects(&self, ctx: &mut Context<'tcx>, expr: ExprId) {
+        let mut effects = Effects {
+            tcx: self.tcx,
+            param_env: self.tcx.param_env(self.sig.def_id),
+            thir: self.thir,
+            found: None,
+        };
+        effects.visit_expr(&self.thir[expr]);
+        if let Some(effect) = effects.found {
+            ctx.unsupported = Some(effect);
+        }
+    }
+    /// A scrutinee which is a variable (or a place within one) is handled by `bind`, since
+    /// what the pattern moves out of it is replaced by the bindings.
+    fn check_scrutinee(&self, ctx: &mut Context<'tcx>, scrutinee: ExprId) {
+        if !local_place(self.thir, scrutinee) {
+            self.check_effects(ctx, scrutinee);
+        }
+    }
+
+    fn descope(&self, mut expr: ExprId) -> ExprId {
+        while let ExprKind::Scope { value: inner, .. } | ExprKind::Use { source: inner } =
+            self.thir[expr].kind
+        {
+            expr = inner;
+        }
+        expr
+    }
+
+    /// Translate side-effect free expressions which `contract_translator` supports.
+    fn try_translate(&self, expr: ExprId, views: &VarMap<'tcx>) -> Option<PureExpression<'tcx>> {
+        self.is_simple(expr).then(|| {
+            thir_expr_to_expr(
+                self.tcx,
+                self.sig.def_id.expect_local(),
+                self.thir,
+                views.clone(),
+                expr,
+            )
+        })
+    }
+    fn is_simple(&self, expr: ExprId) -> bool {
+        match self.thir[expr].kind {
+            ExprKind::Scope { value: e, .. }
+            | ExprKind::Use { source: e }
+            | ExprKind::Unary { arg: e, .. }
+            | ExprKind::Field { lhs: e, .. } => self.is_simple(e),
+            ExprKind::Deref { arg } => self.thir[arg].ty.is_ref() && self.is_simple(arg),
+            ExprKind::Binary { op, lhs, rhs } => {
+                !matches!(op.to_hir_binop(), BinOpKind::Div | BinOpKind::Rem)
+                    && self.is_simple(lhs)
+                    && self.is_simple(rhs)
+            }
+            ExprKind::LogicalOp { lhs, rhs, .. } => self.is_simple(lhs) && self.is_simple(rhs),
+            ExprKind::VarRef { .. } | ExprKind::Literal { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// Is `expr` a place based on a local variable, e.g. `x.f` or `*x`.
+fn local_place(thir: &Thir, expr: ExprId) -> bool {
+    is_local_place(thir, &thir[expr])
+}
+fn is_local_place<'a, 'tcx>(thir: &'a Thir<'tcx>, mut expr: &'a Expr<'tcx>) -> bool {
+    loop {
+        match expr.kind {
+            ExprKind::Scope { value: inner, .. }
+            | ExprKind::Field { lhs: inner, .. }
+            | ExprKind::Index { lhs: inner, .. }
+            | ExprKind::Deref { arg: inner } => expr = &thir[inner],
+            ExprKind::VarRef { .. } | ExprKind::UpvarRef { .. } => return true,
+            _ => return false,
+        }
+    }
+}
+
+/// Finds the first effect of an expression on the variables in scope which is not tracked
+/// by the `Context`: moving out of, mutably borrowing or assigning to a variable. Calls are
+/// fine, since their arguments are checked (e.g. the `&mut x` of `x.push(1)`).
+struct Effects<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    param_env: ParamEnv<'tcx>,
+    thir: &'a Thir<'tcx>,
+    found: Option<&'static str>,
+}
+impl<'a, 'tcx> Visitor<'a, 'tcx> for Effects<'a, 'tcx> {
+    fn thir(&self) -> &'a Thir<'tcx> {
+        self.thir
+    }
+    fn visit_expr(&mut self, expr: &Expr<'tcx>) {
+        match expr.kind {
+            ExprKind::Borrow { borrow_kind, arg } if local_place(self.thir, arg) => {
+                if borrow_kind != BorrowKind::Shared {
+                    self.found = Some("mutable borrow of a variable");
+                }
+            }
+            ExprKind::AddressOf { mutability, arg } if local_place(self.thir, arg) => {
+                if mutability == Mutability::Mut {
+                    self.found = Some("mutable borrow of a variable");
+                }
+            }
+            ExprKind::Assign { lhs, .. } | ExprKind::AssignOp { lhs, .. }
+                if local_place(self.thir, lhs) =>
+            {
+                self.found = Some("assignment to a variable")
+            }
+            ExprKind::Scope { value, .. } => self.visit_expr(&self.thir[value]),
+            ExprKind::VarRef { .. }
+            | ExprKind::UpvarRef { .. }
+            | ExprKind::Field { .. }
+            | ExprKind::Index { .. }
+            | ExprKind::Deref { .. }
+                if is_local_place(self.thir, expr) =>
+            {
+                if !expr
+                    .ty
+                    .is_copy_modulo_regions(self.tcx.at(expr.span), self.param_env)
+                {
+                    self.found = Some("move out of a variable");
+                }
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+struct Binding<'tcx> {
+    name: Symbol,
+    hir_id: rustc_hir::HirId,
+    mode: BindingMode,
+    ty: Ty<'tcx>,
+}
+fn collect_bindings<'tcx>(pat: &Pat<'tcx>, bindings: &mut Vec<Binding<'tcx>>) {
+    match &pat.kind {
+        PatKind::Binding {
+            name,
+            var,
+            mode,
+            ty,
+            subpattern,
+            ..
+        } => {
+            bindings.push(Binding {
+                name: *name,
+                hir_id: var.0,
+                mode: *mode,
+                ty: *ty,
+            });
+            if let Some(subpattern) = subpattern {
+                collect_bindings(subpattern, bindings);
+            }
+        }
+        PatKind::Variant { subpatterns, .. } | PatKind::Leaf { subpatterns } => {
+            for fp in subpatterns {
+                collect_bindings(&fp.pattern, bindings);
+            }
+        }
+        PatKind::Deref { subpattern } | PatKind::AscribeUserType { subpattern, .. } => {
+            collect_bindings(subpattern, bindings)
+        }
+        PatKind::Or { pats } => {
+            // All alternatives bind the same variables
+            if let Some(pat) = pats.first() {
+                collect_bindings(pat, bindings)
+            }
+        }
+        PatKind::Slice {
+            prefix,
+            slice,
+            suffix,
+        }
+        | PatKind::Array {
+            prefix,
+            slice,
+            suffix,
+        } => {
+            for pat in prefix.iter().chain(slice).chain(suffix.iter()) {
+                collect_bindings(pat, bindings);
+            }
+        }
+        PatKind::Wild | PatKind::Constant { .. } | PatKind::Range(_) => (),
+    }
+}
+
+/// Replaces moved variables by what they were matched to. Fields of the reconstructed value
+/// are projected out, any other use of it (e.g. its discriminant) cannot be expressed.
+struct Substitute<'a, 'tcx> {
+    moved: &'a FxHashMap<Var, Option<PureExpression<'tcx>>>,
+    failed: bool,
+}
+impl<'a, 'tcx> Substitute<'a, 'tcx> {
+    fn apply(&mut self, e: &mut PureExpression<'tcx>) -> bool {
+        self.failed = false;
+        e.walk_mut(self);
+        if !self.failed {
+            e.walk_mut(&mut NoConstructor(&mut self.failed));
+        }
+        !self.failed
+    }
+}
+impl<'a, 'tcx> PureExpressionWalker<'tcx> for Substitute<'a, 'tcx> {
+    fn walk_expr_mut(&mut self, e: &mut PureExpression<'tcx>) {
+        if let PExprKind::Var(v) = e.kind() && let Some(value) = self.moved.get(v) {
+            match value {
+                Some(value) => *e = value.clone(),
+                None => self.failed = true,
+            }
+            return;
+        }
+        e.walk_mut(self);
+        if let PExprKind::Field(inner, v, f) = e.kind()
+            && let PExprKind::Constructor(_, vid, fields) = inner.kind()
+        {
+            match fields.iter().find(|(fd, _)| fd == f).filter(|_| vid == v) {
+                Some((_, value)) => *e = value.clone(),
+                None => self.failed = true,
+            }
+        }
+    }
+    fn walk_kind_mut(&mut self, k: &mut PExprKind<'tcx>) {
+        k.walk_mut(self);
+    }
+}
+struct NoConstructor<'a>(&'a mut bool);
+impl<'a, 'tcx> PureExpressionWalker<'tcx> for NoConstructor<'a> {
+    fn walk_expr_mut(&mut self, e: &mut PureExpression<'tcx>) {
+        if let PExprKind::Constructor(..) = e.kind() {
+            *self.0 = true;
+        }
+        e.walk_mut(self);
+    }
+    fn walk_kind_mut(&mut self, k: &mut PExprKind<'tcx>) {
+        k.walk_mut(self);
+    }
+}
+
+fn conjuncts(expr: PureExpression) -> Vec<PureExpression> {
+    if expr.is_true() {
+        return Vec::new();
+    }
+    let ty = expr.ty();
+    match expr.get_kind() {
+        PExprKind::BinOp(BinOpKind::And, l, r) => {
+            let mut l = conjuncts(*l);
+            l.extend(conjuncts(*r));
+            l
+        }
+        other => vec![other.with_ty(ty)],
+    }
+}
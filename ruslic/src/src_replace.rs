@@ -1,128 +1,327 @@
+use std::{
+    io::Write,
+    ops::Range,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use rustc_data_structures::{fx::FxHashMap, sync::Lrc};
 use rustc_hir::def_id::DefId;
 use rustc_middle::ty::TyCtxt;
+use rustc_span::{FileName, RealFileName, SourceFile, Span};
 
-pub fn replace_with_sln<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId, sln: String, keep_newlines: bool) {
-    let span: rustc_span::Span = tcx.source_span(def_id.expect_local());
-    let sm = tcx.sess.source_map();
-    let flines = sm.span_to_lines(span).unwrap();
-    let fname = if let rustc_span::FileName::Real(rustc_span::RealFileName::LocalPath(fname)) =
-        &flines.file.name
-    {
-        fname
-    } else {
-        panic!()
-    };
-    let src = std::fs::read_to_string(fname).unwrap();
-    let (first, last) = (flines.lines.first().unwrap(), flines.lines.last().unwrap());
-    let (fline, lline) = (first.line_index, last.line_index + 1);
-    let mut method = src.split('\n').into_iter().skip(fline).take(lline - fline);
-    // let debug = src.split('\n').into_iter().skip(fline).take(lline - fline).fold(String::new(), |acc, line| acc + line + "\n");
-    // eprintln!("Fn ({:?} {:?}):\n{debug}", first, last);
-    let line1 = method.next().unwrap();
-    // let mut last_line_len = line1.len();
-    // line1[first.start_col.0..].to_string()
-    let method = method.fold(line1.to_string(), |acc, line| {
-        // last_line_len = line.len();
-        acc + "\n" + line
-    });
-    // eprintln!("{last_line_len} vs {}", last.end_col.0);
-    // let to_cut_off = last_line_len-last.end_col.0;
-    // let method = &method[..method.len()-to_cut_off];
-    // let fn_sig = format!("fn {}", tcx.item_name(def_id).as_str());
-    // eprintln!("Fn sig: {fn_sig} and method:\n{method}");
-    // let mut split = method.split(&fn_sig);
-    // let body = if let (_, Some(body), None) = (split.next(), split.next(), split.next()) {
-    //     body
-    // } else { panic!() };
-    let mut braces = 0;
-    let mut curly_braces = 0;
-    let mut in_body = 0;
-    let mut in_comment = 0;
-    let body: String = method
-        .chars()
-        .into_iter()
-        .filter(|c| {
-            if in_comment == 1 {
-                if *c == '/' {
-                    in_comment = 2
-                } else {
-                    in_comment = 0
-                }
-            } else if *c == '\n' {
-                in_comment = 0
+use crate::config::{Emit, RuslicConfig};
+
+/// Lines of context around each hunk of `Emit::Diff`
+const DIFF_CONTEXT: usize = 3;
+
+/// Solutions to be substituted into the source files. Edits are only collected during synthesis
+/// and written out together by `apply` at the end, so that the spans of all edits refer to the
+/// files as they were compiled.
+pub struct SourceEdits {
+    files: FxHashMap<PathBuf, FileEdits>,
+    /// The arguments of the current compiler session, to `check` solutions with
+    args: Vec<String>,
+}
+
+struct FileEdits {
+    file: Lrc<SourceFile>,
+    /// Byte ranges into the original file (as on disk) and their replacements
+    edits: Vec<(Range<usize>, String)>,
+}
+
+impl SourceEdits {
+    pub fn new(args: &[String]) -> Self {
+        Self {
+            files: FxHashMap::default(),
+            args: args.to_vec(),
+        }
+    }
+
+    /// Where the solution for the fn `def_id` goes: its body, or the `ruslik!()` hole at `sketch`.
+    pub fn target_span(tcx: TyCtxt, def_id: DefId, sketch: Option<Span>) -> Span {
+        sketch.unwrap_or_else(|| {
+            let body = tcx.hir().body_owned_by(def_id.expect_local());
+            tcx.hir().body(body).value.span
+        })
+    }
+
+    /// Replace the block or `ruslik!()` at `span` by the block `sln`.
+    pub fn replace(&mut self, tcx: TyCtxt, span: Span, sln: String) {
+        if let Some((path, file, range, block)) = Self::edit(tcx, span, sln) {
+            self.files
+                .entry(path)
+                .or_insert_with(|| FileEdits {
+                    file,
+                    edits: Vec::new(),
+                })
+                .edits
+                .push((range, block));
+        }
+    }
+
+    /// Whether the crate still type and borrow checks after the edits so far and replacing
+    /// `span` by `sln`.
+    pub fn check(&self, tcx: TyCtxt, span: Span, sln: String) -> bool {
+        let (path, file, range, block) = match Self::edit(tcx, span, sln) {
+            Some(edit) => edit,
+            None => return false,
+        };
+        let src = match std::fs::read_to_string(&path) {
+            Ok(src) if file.src_hash.matches(&src) => src,
+            _ => return false,
+        };
+        let mut edits = self
+            .files
+            .get(&path)
+            .map(|f| f.edits.clone())
+            .unwrap_or_default();
+        edits.push((range, block));
+        edits.sort_by_key(|(range, _)| range.start);
+        let replaced = [(path, splice(&src, &edits))].into_iter().collect();
+        crate::recheck::check_with(&self.args, replaced)
+    }
+
+    fn edit(
+        tcx: TyCtxt,
+        span: Span,
+        sln: String,
+    ) -> Option<(PathBuf, Lrc<SourceFile>, Range<usize>, String)> {
+        let sm = tcx.sess.source_map();
+        let lo = sm.lookup_byte_offset(span.lo());
+        let file = lo.sf;
+        let path = match &file.name {
+            FileName::Real(RealFileName::LocalPath(path)) => path.clone(),
+            name => {
+                tcx.sess.span_warn(
+                    span,
+                    &format!("cannot substitute solution into `{name:?}`, it is not a local file"),
+                );
+                return None;
             }
-            if in_comment == 0 {
-                match c {
-                    '/' => in_comment = 1,
-                    '(' => braces += 1,
-                    ')' => braces -= 1,
-                    '{' => {
-                        if braces == 0 && curly_braces == 0 {
-                            in_body += 1;
-                        }
-                        curly_braces += 1
-                    }
-                    '}' => {
-                        curly_braces -= 1;
-                        if braces == 0 && curly_braces == 0 {
-                            in_body += 1;
-                            return true;
-                        }
+        };
+        let start = file.original_relative_byte_pos(span.lo()).0 as usize;
+        let end = file.original_relative_byte_pos(span.hi()).0 as usize;
+        // Indent the solution to the line it is placed on
+        let line = file.lookup_line(span.lo()).unwrap();
+        let indent: String = file
+            .get_line(line)
+            .unwrap()
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect();
+        let block = format_block(tcx, &format!("{{{sln}}}"), &indent);
+        Some((path, file, start..end, block))
+    }
+
+    /// Write all edits to disk, or output them as configured by `emit`. Files which changed
+    /// since they were compiled are left untouched.
+    pub fn apply(self, tcx: TyCtxt, config: &RuslicConfig) {
+        let mut files: Vec<_> = self.files.into_iter().collect();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut out = String::new();
+        for (path, FileEdits { file, mut edits }) in files {
+            let src = std::fs::read_to_string(&path).unwrap();
+            if !file.src_hash.matches(&src) {
+                tcx.sess.warn(&format!(
+                    "not substituting solutions into `{}`, it was modified during synthesis",
+                    path.display()
+                ));
+                continue;
+            }
+            edits.sort_by_key(|(range, _)| range.start);
+            match config.emit {
+                Emit::Write => write_atomic(&path, &splice(&src, &edits)),
+                Emit::Diff => out += &unified_diff(&path, &src, &edits),
+                Emit::Json => {
+                    for (range, replacement) in edits {
+                        let edit = JsonEdit {
+                            file: &path,
+                            range,
+                            replacement,
+                        };
+                        out += &serde_json::to_string(&edit).unwrap();
+                        out.push('\n');
                     }
-                    _ => (),
                 }
-            };
-            in_body == 1
+            }
+        }
+        match &config.emit_path {
+            _ if out.is_empty() => (),
+            Some(emit_path) => std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(emit_path)
+                .and_then(|mut f| f.write_all(out.as_bytes()))
+                .unwrap_or_else(|e| {
+                    tcx.sess.err(&format!(
+                        "failed to write to `{}`: {e}",
+                        emit_path.display()
+                    ));
+                }),
+            None => print!("{out}"),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct JsonEdit<'a> {
+    file: &'a Path,
+    range: Range<usize>,
+    replacement: String,
+}
+
+/// Format `block` with rustfmt as the body of a fn, so that nothing around it is touched. Falls
+/// back to just indenting it if rustfmt is unavailable or fails.
+fn format_block(tcx: TyCtxt, block: &str, indent: &str) -> String {
+    const PREFIX: &str = "fn f() ";
+    let formatted = Command::new("rustfmt")
+        .arg("--edition")
+        .arg(tcx.sess.edition().to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .and_then(|mut rustfmt| {
+            let mut stdin = rustfmt.stdin.take().unwrap();
+            stdin.write_all(format!("{PREFIX}{block}\n").as_bytes())?;
+            drop(stdin);
+            rustfmt.wait_with_output()
         })
-        .collect();
-    assert_eq!(
-        in_body, 2,
-        "Couldn't find {{ body }} in ({span:?})\n{method}"
-    );
-    assert_eq!(
-        method.matches(&body).count(),
-        1,
-        "Looking for {body} in\n{method}"
-    );
-    let sln = sln.replace('\n', &format!("\n{}", " ".repeat(first.start_col.0)));
-    let mut new_method = method.replace(&body, &format!("{{{}}}", sln));
-    if keep_newlines {
-        let (new_method_lines, method_lines) = (
-            new_method.matches('\n').count(),
-            method.matches('\n').count(),
-        );
-        match new_method_lines.cmp(&method_lines) {
-            std::cmp::Ordering::Less => {
-                for _ in 0..(method_lines - new_method_lines) {
-                    new_method += "\n";
-                }
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .and_then(|f| Some(f.strip_prefix(PREFIX)?.trim_end().to_string()));
+    formatted
+        .unwrap_or_else(|| block.to_string())
+        .replace('\n', &format!("\n{indent}"))
+}
+
+fn splice(src: &str, edits: &[(Range<usize>, String)]) -> String {
+    let mut new_src = String::with_capacity(src.len());
+    let mut pos = 0;
+    for (range, replacement) in edits {
+        assert!(pos <= range.start, "overlapping edits");
+        new_src.push_str(&src[pos..range.start]);
+        new_src.push_str(replacement);
+        pos = range.end;
+    }
+    new_src.push_str(&src[pos..]);
+    new_src
+}
+
+/// A unified diff of applying the (sorted) `edits` to `src`, with paths relative to the
+/// current directory so that it can be applied with `git apply` or `patch -p1`.
+fn unified_diff(path: &Path, src: &str, edits: &[(Range<usize>, String)]) -> String {
+    let lines: Vec<_> = src.split_inclusive('\n').collect();
+    let line_of = |pos: usize| {
+        let mut start = 0;
+        lines
+            .iter()
+            .position(|line| {
+                start += line.len();
+                pos < start
+            })
+            .unwrap_or(lines.len() - 1)
+    };
+    let line_start = |line: usize| lines[..line].iter().map(|l| l.len()).sum::<usize>();
+    // Changed line ranges, edits touching the same line are merged
+    type Change<'a> = (Range<usize>, Vec<&'a (Range<usize>, String)>);
+    let mut changes: Vec<Change> = Vec::new();
+    for edit in edits {
+        let changed = line_of(edit.0.start)..line_of(edit.0.end.max(edit.0.start + 1) - 1) + 1;
+        match changes.last_mut() {
+            Some((last, group)) if changed.start < last.end => {
+                last.end = last.end.max(changed.end);
+                group.push(edit);
             }
-            std::cmp::Ordering::Equal => (),
-            std::cmp::Ordering::Greater => {
-                new_method = new_method.replacen('\n', "", new_method_lines - method_lines);
+            _ => changes.push((changed, vec![edit])),
+        }
+    }
+    // Hunks are changes whose context overlaps
+    let mut hunks: Vec<(Range<usize>, Vec<Change>)> = Vec::new();
+    for (changed, group) in changes {
+        let context = changed.start.saturating_sub(DIFF_CONTEXT)
+            ..(changed.end + DIFF_CONTEXT).min(lines.len());
+        match hunks.last_mut() {
+            Some((last, hunk)) if context.start <= last.end => {
+                last.end = context.end;
+                hunk.push((changed, group));
             }
+            _ => hunks.push((context, vec![(changed, group)])),
         }
-        assert_eq!(
-            new_method.matches('\n').count(),
-            method.matches('\n').count()
-        );
     }
 
-    // let new_src = src.replace(&method, &new_method);
-    let pre = src.split('\n').take(fline);
-    let post = src.split('\n').skip(lline);
-    let mut total = pre.chain(new_method.split('\n')).chain(post);
-    let init = total.next().unwrap().to_string();
-    let new_src = total.fold(init, |acc, line| acc + "\n" + line);
-    // println!("{src}\nVS\n{new_src}");
-    if keep_newlines {
-        assert_eq!(
-            src.matches('\n').count(),
-            new_src.matches('\n').count(),
-            "{fname:?} ({:?} {:?})\n{src}\n-- VS --\n{new_src}",
-            first,
-            last
+    let path = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .unwrap_or(path);
+    let mut diff = format!("--- a/{0}\n+++ b/{0}\n", path.display());
+    let push_line = |diff: &mut String, prefix: char, line: &str| {
+        diff.push(prefix);
+        diff.push_str(line);
+        if !line.ends_with('\n') {
+            diff.push_str("\n\\ No newline at end of file\n");
+        }
+    };
+    // Lines added minus lines removed by previous hunks
+    let mut offset: isize = 0;
+    for (context, hunk) in hunks {
+        let mut body = String::new();
+        let (mut old_len, mut new_len) = (0, 0);
+        let mut line = context.start;
+        for (changed, group) in hunk {
+            for context_line in &lines[line..changed.start] {
+                push_line(&mut body, ' ', context_line);
+            }
+            old_len += changed.start - line;
+            new_len += changed.start - line;
+            let start = line_start(changed.start);
+            let end = start
+                + lines[changed.clone()]
+                    .iter()
+                    .map(|l| l.len())
+                    .sum::<usize>();
+            let shifted: Vec<_> = group
+                .iter()
+                .map(|(range, replacement)| {
+                    (
+                        (range.start - start)..(range.end - start),
+                        replacement.clone(),
+                    )
+                })
+                .collect();
+            let new = splice(&src[start..end], &shifted);
+            for old_line in &lines[changed.clone()] {
+                push_line(&mut body, '-', old_line);
+            }
+            for new_line in new.split_inclusive('\n') {
+                push_line(&mut body, '+', new_line);
+                new_len += 1;
+            }
+            old_len += changed.len();
+            line = changed.end;
+        }
+        for context_line in &lines[line..context.end] {
+            push_line(&mut body, ' ', context_line);
+        }
+        old_len += context.end - line;
+        new_len += context.end - line;
+        let new_start = context.start as isize + 1 + offset;
+        diff += &format!(
+            "@@ -{},{old_len} +{new_start},{new_len} @@\n{body}",
+            context.start + 1
         );
+        offset += new_len as isize - old_len as isize;
     }
-    std::fs::write(fname, &new_src).unwrap();
+    diff
+}
+
+/// Write to a temporary file next to `path` and then rename it, so that the file is never
+/// observed half written.
+fn write_atomic(path: &Path, contents: &str) {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".ruslic-tmp");
+    std::fs::write(&tmp, contents).unwrap();
+    std::fs::rename(&tmp, path).unwrap();
 }
@@ -0,0 +1,138 @@
+//! The built-in `#[extern_spec]`s of std fns (`Option::take`, `Box::new`, `std::mem::replace`,
+//! ...), from `std-specs/prelude.rs`. These are parsed into a module of the crate being checked
+//! right after parsing, so that they are type checked and translated like any user written
+//! extern spec. After translation, entries overridden by the crate itself or skipped with
+//! `--skip-std-specs` are dropped again.
+
+use rustc_ast::{ast, ptr::P};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::TyCtxt;
+use rustc_parse::parser::ForceCollect;
+use rustc_session::Session;
+use rustc_span::{sym, FileName};
+
+use crate::{
+    config::RuslicConfig, hir_translator::extern_path, interner::glob_matches,
+    ruslik_types::RuslikFnSig,
+};
+
+const PRELUDE: &str = include_str!("../std-specs/prelude.rs");
+/// The name of the injected module
+const MODULE: &str = "__russol_std_specs";
+const CONTRACTS: &str = "russol_contracts";
+
+/// Adds the prelude as a module to `krate`, unless it is `#![no_std]` (the specs call `std`).
+pub(crate) fn inject(sess: &Session, krate: &mut ast::Crate) {
+    let mut attrs = krate.attrs.iter().filter_map(|attr| attr.meta());
+    if attrs.any(|meta| is_no_std(sess, &meta)) {
+        return;
+    }
+    // The contract macros expand to `::russol_contracts` paths, which are relative to the crate
+    // root in 2015 crates
+    if sess.rust_2015()
+        && !krate
+            .items
+            .iter()
+            .any(|item| item.ident.as_str() == CONTRACTS)
+    {
+        let src = format!("#[allow(unused_extern_crates)] extern crate {CONTRACTS};");
+        krate
+            .items
+            .extend(parse_item(sess, "russol contracts", src));
+    }
+    let src = format!("#[allow(unused)] mod {MODULE} {{\n{PRELUDE}}}");
+    krate
+        .items
+        .extend(parse_item(sess, "russol std specs", src));
+}
+
+/// Whether `meta` is `no_std`, also if it is behind a `cfg_attr` which holds (crate attributes
+/// are only `cfg_attr` expanded after parsing).
+fn is_no_std(sess: &Session, meta: &ast::MetaItem) -> bool {
+    if meta.has_name(sym::no_std) {
+        return true;
+    }
+    if !meta.has_name(sym::cfg_attr) {
+        return false;
+    }
+    match meta.meta_item_list() {
+        Some([cfg, attrs @ ..]) => {
+            cfg.meta_item().map_or(false, |cfg| {
+                rustc_attr::cfg_matches(cfg, &sess.parse_sess, ast::CRATE_NODE_ID, None)
+            }) && attrs
+                .iter()
+                .filter_map(|attr| attr.meta_item())
+                .any(|attr| is_no_std(sess, attr))
+        }
+        _ => false,
+    }
+}
+
+/// Parses `src`, which is added to the source map as `name` (files are reused by name).
+fn parse_item(sess: &Session, name: &str, src: String) -> Option<P<ast::Item>> {
+    let name = FileName::Custom(name.to_string());
+    let mut parser = rustc_parse::new_parser_from_source_str(&sess.parse_sess, name, src);
+    match parser.parse_item(ForceCollect::No) {
+        Ok(item) => item,
+        Err(mut err) => {
+            err.emit();
+            None
+        }
+    }
+}
+
+/// Whether `def_id` is one of the injected prelude fns.
+pub(crate) fn is_std_spec(tcx: TyCtxt, def_id: DefId) -> bool {
+    def_id.is_local()
+        && tcx
+            .def_path(def_id)
+            .data
+            .first()
+            .map_or(false, |data| data.data.to_string() == MODULE)
+}
+
+/// What an extern spec is keyed by: the path which `#[extern_spec(path)]` names (or otherwise the
+/// fn itself), and its self type, i.e. the ADT which its first argument is (a reference to).
+fn spec_key(tcx: TyCtxt, def_id: DefId) -> (String, Option<DefId>) {
+    let path = extern_path(tcx, def_id).unwrap_or_else(|| tcx.item_name(def_id).to_string());
+    let sig = tcx.fn_sig(def_id).skip_binder();
+    let self_ty = sig
+        .inputs()
+        .first()
+        .and_then(|ty| ty.peel_refs().ty_adt_def())
+        .map(|adt| adt.did());
+    (path, self_ty)
+}
+
+/// Whether one of the paths is the other with some leading segments dropped, e.g. `Option::take`
+/// and `std::option::Option::take`.
+fn same_path(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    long.strip_suffix(short)
+        .map_or(false, |prefix| prefix.is_empty() || prefix.ends_with("::"))
+}
+
+/// Drops the prelude entries which the crate overrides with an extern spec of the same path and
+/// self type (so that `fn take(v: &mut Vec<T>)` does not drop `Option::take`), or which match one
+/// of the `--skip-std-specs` globs.
+pub(crate) fn retain<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    config: &RuslicConfig,
+    extern_fns: &mut Vec<RuslikFnSig<'tcx>>,
+) {
+    let overridden: Vec<_> = extern_fns
+        .iter()
+        .filter(|f| !is_std_spec(tcx, f.def_id))
+        .map(|f| spec_key(tcx, f.def_id))
+        .collect();
+    extern_fns.retain(|f| {
+        if !is_std_spec(tcx, f.def_id) {
+            return true;
+        }
+        let (path, self_ty) = spec_key(tcx, f.def_id);
+        !overridden
+            .iter()
+            .any(|(p, ty)| *ty == self_ty && same_path(p, &path))
+            && !config.skip_std_specs.iter().any(|g| glob_matches(g, &path))
+    });
+}
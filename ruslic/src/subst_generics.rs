@@ -1,10 +1,13 @@
 use std::ops::ControlFlow;
 
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_hir::def_id::DefId;
+use rustc_infer::infer::TyCtxtInferExt;
 use rustc_middle::ty::{
-    GenericParamDef, ParamTy, SubstsRef, Ty, TyCtxt, TyKind, TypeFolder, TypeSuperFoldable,
-    TypeVisitable, TypeVisitor,
+    GenericParamDef, ParamEnv, ParamTy, PredicateKind, SubstsRef, Ty, TyCtxt, TyKind,
+    TypeFoldable, TypeFolder, TypeSuperFoldable, TypeVisitable, TypeVisitor,
 };
+use rustc_trait_selection::infer::InferCtxtExt;
 
 use crate::{
     ruslik_pure::PureExpression, ruslik_pure_helpers::PureExpressionWalker,
@@ -213,6 +216,8 @@ impl VecIter2 {
 pub struct SGenericsCollector<'tcx> {
     pub tcx: TyCtxt<'tcx>,
     pub synth_tys: FxHashSet<Ty<'tcx>>,
+    /// Of the fn being synthesized, under which the trait bounds of extern fns must hold
+    pub param_env: ParamEnv<'tcx>,
 }
 impl<'tcx> SGenericsCollector<'tcx> {
     pub fn find_subs_for_ext_fns(
@@ -312,6 +317,9 @@ impl<'tcx> SGenericsCollector<'tcx> {
                 .map(|(gp, (idx, tys))| (*gp, tys[perm[*idx]]))
                 .collect();
             // print!("{:?}, ", substs);
+            if !self.bounds_hold(extern_fn.def_id, &substs) {
+                continue;
+            }
             let substs_name = substs
                 .values()
                 .map(|ty| crate::suslik_translate::sanitize(&ty.to_string()))
@@ -324,4 +332,32 @@ impl<'tcx> SGenericsCollector<'tcx> {
         // println!("");
         possible_perms
     }
+
+    /// Whether the trait bounds of `def_id` (e.g. `T: Clone`) hold with `substs`. Params not
+    /// in `substs` (which don't appear in the signature) are left as they are.
+    fn bounds_hold(&self, def_id: DefId, substs: &FxHashMap<u32, Ty<'tcx>>) -> bool {
+        let tcx = self.tcx;
+        let mut folder = SubstFolder {
+            tcx,
+            subst: Box::new(|p| substs.get(&p.index).copied().unwrap_or_else(|| p.to_ty(tcx))),
+        };
+        let predicates = tcx.predicates_of(def_id).instantiate_identity(tcx);
+        predicates.predicates.into_iter().all(|pred| {
+            let trait_pred = match pred.kind().no_bound_vars() {
+                Some(PredicateKind::Trait(trait_pred)) => trait_pred,
+                _ => return true,
+            };
+            let self_ty = folder.fold_ty(trait_pred.self_ty());
+            let params = tcx.mk_substs(
+                trait_pred.trait_ref.substs[1..]
+                    .iter()
+                    .map(|arg| arg.fold_with(&mut folder)),
+            );
+            tcx.infer_ctxt().enter(|infcx| {
+                infcx
+                    .type_implements_trait(trait_pred.def_id(), self_ty, params, self.param_env)
+                    .must_apply_modulo_regions()
+            })
+        })
+    }
 }
@@ -1,5 +1,4 @@
 use std::{
-    path::PathBuf,
     process::{Command, Stdio},
     sync::mpsc::Sender,
     time::{Duration, Instant},
@@ -7,16 +6,19 @@ use std::{
 
 use rustc_ast::LitIntType;
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
-use rustc_hir::def::DefKind;
+use rustc_hir::{def::DefKind, def_id::DefId};
 use rustc_middle::ty::{Ty, TyCtxt, TyKind};
-use rustc_type_ir::{IntTy, UintTy};
 use wait_timeout::ChildExt;
 
 use crate::{
-    hir_translator::{PureFn, PureFnMap},
+    config::RuslicConfig,
+    hir_translator::{PureFn, PureFnMap, TraitSpecMap},
+    int_ops::{int_fns, int_width},
     ruslik_types::RuslikFnSig,
     subst_generics::SGenericsCollector,
-    suslik_translate::{outlives_relations, ExprTranslator, STyTranslator},
+    suslik_jar::SuslikJar,
+    suslik_trace::Explanation,
+    suslik_translate::{outlives_relations, ExprTranslator, SeqSplits, STyTranslator, TyError},
     trait_bounds::find_trait_fns,
 };
 
@@ -42,6 +44,7 @@ pub type UnOp = rustc_middle::mir::UnOp;
 pub enum Lit {
     Int(u128, rustc_ast::ast::LitIntType),
     Bool(bool),
+    /// Something which SuSLik cannot express, makes the fn unsupported (see `Expr::unsupported`)
     Unsupported(String),
 }
 impl From<&rustc_ast::ast::LitKind> for Lit {
@@ -49,10 +52,25 @@ impl From<&rustc_ast::ast::LitKind> for Lit {
         match lit {
             &rustc_ast::ast::LitKind::Int(i, t) => Lit::Int(i, t),
             &rustc_ast::ast::LitKind::Bool(b) => Lit::Bool(b),
+            // Strings are abstract, a literal is the snapshot given by its id in `STR_LITS`
+            rustc_ast::ast::LitKind::Str(s, _) => {
+                Lit::Int(str_lit_id(s.as_str()), rustc_ast::ast::LitIntType::Unsuffixed)
+            }
             other => Lit::Unsupported(format!("{other:?}")),
         }
     }
 }
+/// The string literals seen in specs, the index of each is its snapshot. These are small
+/// since SuSLik only parses integer literals which fit in an `Int`.
+static STR_LITS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+fn str_lit_id(s: &str) -> u128 {
+    let mut lits = STR_LITS.lock().unwrap();
+    let id = lits.iter().position(|lit| lit == s).unwrap_or_else(|| {
+        lits.push(s.to_string());
+        lits.len() - 1
+    });
+    id as u128
+}
 // pub type Lit = rustc_ast::ast::LitKind;
 
 pub struct SuslikProgram {
@@ -79,6 +97,9 @@ pub struct Predicate {
     pub is_private: bool,
     pub ident: String, // used as key for Predicate map
     pub clean_name: String,
+    /// The width and signedness of a primitive integer, whose clause constrains it to the
+    /// range of the type
+    pub int_width: Option<(u64, bool)>,
     // Fill in from fn_spec
     pub facts: Phi,
     pub fn_spec: Vec<PredParameter>,
@@ -118,6 +139,7 @@ pub enum FnSpecKind {
     Bool,
     Lft,
     Set,
+    Seq,
     Snap, //, Tpl
 }
 
@@ -131,6 +153,7 @@ impl FnSpecKind {
             TyKind::Tuple(t) if t.is_empty() => Self::Int,
             TyKind::Adt(_, _) => match ty.to_string().as_str() {
                 ty if ty.starts_with("russol_contracts::Set") => Self::Set,
+                ty if ty.starts_with("russol_contracts::Seq") => Self::Seq,
                 _ => todo!(),
             },
             _ => unreachable!(),
@@ -179,6 +202,7 @@ pub enum Expr {
     OnExpiry(Vec<bool>, FnSpecKind, String, Result<usize, String>),
     // true -> is set, false -> is tuple
     Tuple(bool, Vec<Expr>),
+    Seq(Vec<Expr>),
     Lit(Lit),
     BinOp(BinOp, Box<Expr>, Box<Expr>),
     UnOp(UnOp, Box<Expr>),
@@ -215,6 +239,80 @@ impl Sigma {
 pub struct Unsupported {
     pub in_main: bool,
     pub reason: Reason,
+    /// The type (or with `Reason::Spec` the part of the spec) which could not be translated
+    #[serde(default)]
+    pub ty: Option<String>,
+    /// How `ty` is reached from the signature, e.g. `self.next.*.elem`
+    #[serde(default)]
+    pub path: Option<String>,
+    /// The argument (or return type) which `path` starts from
+    #[serde(default)]
+    pub span: Option<String>,
+}
+impl Unsupported {
+    fn new(in_main: bool, reason: Reason) -> Self {
+        Self {
+            in_main,
+            reason,
+            ty: None,
+            path: None,
+            span: None,
+        }
+    }
+    fn from_spec(tcx: TyCtxt, in_main: bool, def_id: DefId, what: &str) -> Self {
+        let span = tcx.def_span(def_id);
+        Self {
+            ty: Some(what.to_string()),
+            span: Some(tcx.sess.source_map().span_to_embeddable_string(span)),
+            ..Self::new(in_main, Reason::Spec)
+        }
+    }
+    fn from_ty_error(tcx: TyCtxt, in_main: bool, def_id: DefId, err: TyError) -> Self {
+        // The first segment is the name of the argument or `result`
+        let span = def_id.as_local().and_then(|local| {
+            let decl = tcx
+                .hir()
+                .fn_decl_by_hir_id(tcx.hir().local_def_id_to_hir_id(local))?;
+            let arg = err.path.first()?;
+            if arg == "result" {
+                return Some(decl.output.span());
+            }
+            let idx = tcx
+                .fn_arg_names(def_id)
+                .iter()
+                .position(|name| name.as_str() == arg)?;
+            decl.inputs.get(idx).map(|ty| ty.span)
+        });
+        let span = span.unwrap_or_else(|| tcx.def_span(def_id));
+        Self {
+            in_main,
+            reason: err.reason,
+            ty: err.ty,
+            path: Some(err.path.join(".")),
+            span: Some(tcx.sess.source_map().span_to_embeddable_string(span)),
+        }
+    }
+
+    /// The `n` types which most often made fns unsupported, with how often and (one of) the
+    /// reasons why.
+    pub fn top_tys<'a>(
+        unsupported: impl Iterator<Item = &'a Unsupported>,
+        n: usize,
+    ) -> Vec<(&'a str, Reason, usize)> {
+        let mut counts: FxHashMap<&str, (Reason, usize)> = FxHashMap::default();
+        for u in unsupported {
+            if let Some(ty) = &u.ty {
+                counts.entry(ty).or_insert((u.reason, 0)).1 += 1;
+            }
+        }
+        let mut counts: Vec<_> = counts
+            .into_iter()
+            .map(|(ty, (reason, count))| (ty, reason, count))
+            .collect();
+        counts.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(b.0)));
+        counts.truncate(n);
+        counts
+    }
 }
 #[derive(serde::Serialize, serde::Deserialize, Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub enum Reason {
@@ -229,20 +327,34 @@ pub enum Reason {
     Closure,
     Unsafe,
     OtherTy,
+    /// The spec uses something SuSLik cannot express
+    Spec,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct SynthesisResult {
     pub is_trivial: bool,
     pub kind: SynthesisResultKind,
+    /// For `Unsolvable` and `Timeout` with `output-trace`, why SuSLik got stuck
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Explanation>,
 }
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub enum SynthesisResultKind {
     Unsupported(Unsupported),
+    /// The spec is contradictory, so SuSLik was not run
+    Vacuous(Vacuity),
     Unsolvable(u64),
     Timeout,
     Solved(Solved),
 }
+#[derive(serde::Serialize, serde::Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Vacuity {
+    /// The precondition (with the ranges of integer arguments) is unsatisfiable
+    Pre,
+    /// The postcondition is unsatisfiable together with the precondition
+    Post,
+}
 impl SynthesisResult {
     pub fn get_solved(&self) -> Option<&Solved> {
         if let SynthesisResultKind::Solved(sln) = &self.kind {
@@ -251,6 +363,13 @@ impl SynthesisResult {
             None
         }
     }
+    pub fn get_solved_mut(&mut self) -> Option<&mut Solved> {
+        if let SynthesisResultKind::Solved(sln) = &mut self.kind {
+            Some(sln)
+        } else {
+            None
+        }
+    }
     pub fn get_unsupported(&self) -> Option<&Unsupported> {
         if let SynthesisResultKind::Unsupported(u) = &self.kind {
             Some(u)
@@ -276,6 +395,9 @@ pub struct Solved {
     pub synth_ast: usize,
     pub pure_fn_ast: UsedPureFns,
     pub slns: Vec<Solution>,
+    /// Index of the solution which was substituted, as picked by the `solution-strategy`
+    #[serde(default)]
+    pub chosen: Option<usize>,
 }
 impl Solved {
     fn new(exec_time: u64, synth_ast: usize, pure_fn_ast: UsedPureFns, sln: String) -> Self {
@@ -289,12 +411,11 @@ impl Solved {
             synth_ast,
             pure_fn_ast,
             slns,
+            chosen: None,
         }
     }
-    pub fn print(&self) {
-        let min_lines_print = std::env::var("RUSLIC_PRINT_SLN_ABOVE")
-            .map(|v| v.parse::<usize>().unwrap())
-            .unwrap_or(0);
+    /// Prints solutions with more than `min_lines_print` lines
+    pub fn print(&self, min_lines_print: usize) {
         for sln in &self.slns {
             if sln.loc > min_lines_print {
                 if self.slns.len() > 1 {
@@ -398,25 +519,34 @@ impl Solution {
             idx: *idx - 1,
         })
     }
+    /// The lines of the body, without the signature and closing brace.
+    pub fn body(&self) -> String {
+        self.code
+            .lines()
+            .skip(1)
+            .take(self.loc)
+            .fold("\n".to_string(), |acc, line| acc + line + "\n")
+    }
 }
 
 impl SuslikProgram {
-    /// Timeout in ms
     pub fn solve<'tcx>(
         tcx: TyCtxt<'tcx>,
+        config: &RuslicConfig,
         sig: RuslikFnSig<'tcx>,
         pure_fns: &PureFnMap<'tcx>,
         extern_fns: &Vec<RuslikFnSig<'tcx>>,
-        timeout: u64,
+        trait_specs: &TraitSpecMap<'tcx>,
+        suslik: &SuslikJar,
     ) -> Option<SynthesisResult> {
-        let suslik_dir = Self::sbt_build_suslik();
         let params = sig.params.clone();
         let is_trivial = sig.is_trivial();
-        match Self::from_fn_sig(tcx, pure_fns, extern_fns, sig) {
-            Ok(sp) => sp.send_to_suslik(suslik_dir, &params, timeout),
+        match Self::from_fn_sig(tcx, config, pure_fns, extern_fns, trait_specs, sig) {
+            Ok(sp) => sp.check_and_send(config, suslik, &params),
             Err(err) => Some(SynthesisResult {
                 is_trivial,
                 kind: SynthesisResultKind::Unsupported(err),
+                explanation: None,
             }),
         }
     }
@@ -424,93 +554,54 @@ impl SuslikProgram {
         tx: Sender<(usize, Option<SynthesisResult>)>,
         id: usize,
         tcx: TyCtxt<'tcx>,
+        config: &RuslicConfig,
         sig: RuslikFnSig<'tcx>,
         pure_fns: &PureFnMap<'tcx>,
         extern_fns: &Vec<RuslikFnSig<'tcx>>,
-        timeout: u64,
+        trait_specs: &TraitSpecMap<'tcx>,
+        suslik: &SuslikJar,
     ) {
-        let suslik_dir = Self::sbt_build_suslik();
+        let config = config.clone();
+        let suslik = suslik.clone();
         let params = sig.params.clone();
         let is_trivial = sig.is_trivial();
-        let sus_prog = Self::from_fn_sig(tcx, pure_fns, extern_fns, sig);
+        let sus_prog = Self::from_fn_sig(tcx, &config, pure_fns, extern_fns, trait_specs, sig);
         std::thread::spawn(move || {
             let result = match sus_prog {
-                Ok(sp) => sp.send_to_suslik(suslik_dir, &params, timeout),
+                Ok(sp) => sp.check_and_send(&config, &suslik, &params),
                 Err(err) => Some(SynthesisResult {
                     is_trivial,
                     kind: SynthesisResultKind::Unsupported(err),
+                    explanation: None,
                 }),
             };
             tx.send((id, result)).unwrap();
         });
     }
-    fn sbt_build_suslik() -> PathBuf {
-        // Find suslik dir
-        let suslik_dir = std::env::var("SUSLIK_DIR")
-            .map(std::path::PathBuf::from)
-            .unwrap_or_else(|_| {
-                let mut suslik_dir = std::env::current_exe().unwrap();
-                while {
-                    suslik_dir.push("suslik");
-                    !suslik_dir.exists()
-                } {
-                    suslik_dir.pop();
-                    if !suslik_dir.pop() {
-                        break;
-                    }
-                }
-                if suslik_dir.parent().is_none() {
-                    suslik_dir = std::env::current_dir().unwrap();
-                    while {
-                        suslik_dir.push("suslik");
-                        !suslik_dir.exists()
-                    } {
-                        suslik_dir.pop();
-                        assert!(
-                            suslik_dir.pop(),
-                            "Failed to find suslik dir in parents of {}",
-                            std::env::current_dir().unwrap().to_string_lossy()
-                        );
-                    }
-                }
-                suslik_dir
-            });
-        // Find suslik exe
-        let mut jar_file = suslik_dir.clone();
-        jar_file.extend(["target", "scala-2.12", "suslik.jar"]);
-        if !jar_file.is_file() {
-            // Need to assmble jar file
-            println!(
-                "Running `sbt assembly` since there is no executable at {}",
-                jar_file.to_string_lossy()
-            );
-            let mut assembly = Command::new(if cfg!(windows) { "cmd" } else { "sbt" });
-            if cfg!(windows) {
-                assembly.arg("/c").arg("sbt");
-            }
-            let mut assembly = assembly
-                .arg("assembly")
-                .current_dir(&suslik_dir)
-                .spawn()
-                .expect("`sbt assembly` command failed to start");
-            assembly.wait().unwrap();
-            assert!(
-                jar_file.is_file(),
-                "Running `sbt assembly` failed to create jar file at {}",
-                jar_file.to_string_lossy()
-            );
+    fn check_and_send(
+        &self,
+        config: &RuslicConfig,
+        suslik: &SuslikJar,
+        params: &str,
+    ) -> Option<SynthesisResult> {
+        match config.check_vacuity.then(|| self.vacuity()).flatten() {
+            Some(vacuity) => Some(SynthesisResult {
+                is_trivial: self.synth_fn.is_trivial,
+                kind: SynthesisResultKind::Vacuous(vacuity),
+                explanation: None,
+            }),
+            None => self.send_to_suslik(config, suslik, params),
         }
-        suslik_dir
     }
-
     fn send_to_suslik(
         &self,
-        suslik_dir: PathBuf,
+        config: &RuslicConfig,
+        suslik: &SuslikJar,
         params: &str,
-        timeout: u64,
     ) -> Option<SynthesisResult> {
         // Write program to tmp file
         let data = format!("# -c 10 -o 10 -p false\n###\n{}", self);
+        let suslik_dir = &suslik.work_dir;
         let mut tmp = suslik_dir.clone();
 
         use rand::Rng;
@@ -529,10 +620,7 @@ impl SuslikProgram {
         if provided_args.iter().all(|a| !a.contains("--solutions=")) {
             provided_args.push("--solutions=1".to_string());
         }
-        let output_trace = std::env::var("RUSLIC_OUTPUT_TRACE")
-            .map(|v| v.parse::<bool>().unwrap())
-            .unwrap_or(false);
-        if output_trace {
+        if config.output_trace {
             let logfile = tmpdir.join(std::path::PathBuf::from("trace.json"));
             let logfile = logfile.to_str();
             provided_args.push("-j".to_string());
@@ -541,17 +629,18 @@ impl SuslikProgram {
         let mut child = Command::new("java")
             .arg("-Dfile.encoding=UTF-8")
             .arg("-jar")
-            .arg("./target/scala-2.12/suslik.jar")
+            .arg(&suslik.jar)
             .arg(&synfile)
             .args(provided_args)
-            .current_dir(&suslik_dir)
+            .current_dir(suslik_dir)
             .stdout(Stdio::piped())
             .spawn()
             .expect("`java` command failed to start");
         let mut stdout = child.stdout.take().unwrap();
         let start = Instant::now();
+        let timeout = config.timeout;
         let max = Duration::from_millis(timeout);
-        let (intime, exit_status, time) = match child.wait_timeout(max).expect("sbt crashed?") {
+        let (intime, exit_status, time) = match child.wait_timeout(max).expect("java crashed?") {
             Some(status) => (true, status, start.elapsed()),
             None => {
                 // child hasn't exited yet
@@ -560,11 +649,8 @@ impl SuslikProgram {
                 (false, child.wait().unwrap(), max)
             }
         };
-        let fail_on_unsynth = std::env::var("RUSLIC_FAIL_ON_UNSYNTH")
-            .map(|v| v.parse::<bool>().unwrap())
-            .unwrap_or(true);
         let unsolvable = exit_status.code().unwrap_or(0) == 2;
-        let failed = !exit_status.success() && (fail_on_unsynth || !unsolvable);
+        let failed = !exit_status.success() && (config.fail_on_unsynth || !unsolvable);
         if intime && failed {
             println!(
                 "suslik failed ({}) for {}",
@@ -572,16 +658,29 @@ impl SuslikProgram {
             );
             None
         } else {
+            // The trace is kept (with the rest of the temp dir) if it explains a failure
+            let explanation = || {
+                let trace = suslik_dir.join(&tmpdir).join("trace.json");
+                config
+                    .output_trace
+                    .then(|| Explanation::from_trace(&trace, &self.rust_names()))
+                    .flatten()
+            };
             Some(if !intime {
                 SynthesisResult {
                     is_trivial: self.synth_fn.is_trivial,
                     kind: SynthesisResultKind::Timeout,
+                    explanation: explanation(),
                 }
             } else if unsolvable {
-                std::fs::remove_dir_all(suslik_dir.join(&tmpdir)).unwrap();
+                let explanation = explanation();
+                if explanation.is_none() {
+                    std::fs::remove_dir_all(suslik_dir.join(&tmpdir)).unwrap();
+                }
                 SynthesisResult {
                     is_trivial: self.synth_fn.is_trivial,
                     kind: SynthesisResultKind::Unsolvable(time.as_millis() as u64),
+                    explanation,
                 }
             } else {
                 std::fs::remove_dir_all(suslik_dir.join(&tmpdir)).unwrap();
@@ -596,6 +695,7 @@ impl SuslikProgram {
                         self.pure_fn_ast.clone(),
                         sln,
                     )),
+                    explanation: None,
                 }
             })
         }
@@ -603,31 +703,46 @@ impl SuslikProgram {
 
     fn from_fn_sig<'a, 'tcx>(
         tcx: TyCtxt<'tcx>,
+        config: &RuslicConfig,
         pure_fns: &'a PureFnMap<'tcx>,
         extern_fns: &Vec<RuslikFnSig<'tcx>>,
+        trait_specs: &TraitSpecMap<'tcx>,
         sig: RuslikFnSig<'tcx>,
     ) -> Result<Self, Unsupported> {
         let def_id = sig.def_id;
         let ast_nodes = sig.ast_nodes;
         let mut map = FxHashMap::default();
-        let ssig = Signature::from_fn_sig(tcx, pure_fns, sig, &mut map)?;
-        let trait_fns = find_trait_fns(tcx, def_id, &ssig.tys);
+        let ssig = Signature::from_fn_sig(tcx, config, pure_fns, sig, &mut map)?;
+        let trait_fns = find_trait_fns(tcx, def_id, &ssig.tys, trait_specs);
 
         let mut efns = trait_fns
             .into_iter()
             .flat_map(|tf| {
-                Signature::from_fn_sig_map(tcx, pure_fns, tf, &mut map, false)
+                Signature::from_fn_sig_map(tcx, config, pure_fns, tf, &mut map, false)
                     .map(|sig| sig.sig)
                     .ok()
             })
             .collect::<Vec<Signature>>();
+        if config.int_components {
+            for (ty, ifn) in int_fns(tcx, ssig.tys.iter().copied()) {
+                if let Ok(sig) = Signature::from_fn_sig_map(tcx, config, pure_fns, ifn, &mut map, false) {
+                    efns.push(Signature { unique_name: sig.sig.unique_name + "_" + &ty, ..sig.sig });
+                }
+            }
+        }
         let sgc = SGenericsCollector {
             tcx,
             synth_tys: ssig.tys,
+            param_env: tcx.param_env(def_id),
         };
         for efn in extern_fns {
             for (gens, efn) in sgc.find_subs_for_ext_fns(efn) {
-                let mut sig = Signature::from_fn_sig_map(tcx, pure_fns, efn, &mut map, false)?;
+                // The std specs are offered where they can be, but never make a fn unsupported
+                let is_std_spec = crate::std_specs::is_std_spec(tcx, efn.def_id);
+                let mut sig = match Signature::from_fn_sig_map(tcx, config, pure_fns, efn, &mut map, false) {
+                    Err(_) if is_std_spec => continue,
+                    sig => sig?,
+                };
                 sig.sig.unique_name = sig.sig.unique_name + "_" + &gens;
                 efns.push(sig.sig);
             }
@@ -649,6 +764,21 @@ impl SuslikProgram {
                 })
                 .collect(),
         };
+        // The pure fns used in specs end up in the predicates of their arguments
+        let in_pure_fn = res
+            .pred_map
+            .values()
+            .flat_map(|pred| {
+                pred.facts.0.iter().chain(
+                    pred.clauses
+                        .iter()
+                        .flat_map(|c| c.equalities.values().chain(&c.assn.phi.0)),
+                )
+            })
+            .find_map(Expr::unsupported);
+        if let Some(what) = in_pure_fn {
+            return Err(Unsupported::from_spec(tcx, true, def_id, what));
+        }
         res.normalize();
         Ok(res)
     }
@@ -663,29 +793,24 @@ pub struct SignatureSuccess<'a, 'tcx> {
 impl Signature {
     pub fn from_fn_sig<'a, 'tcx>(
         tcx: TyCtxt<'tcx>,
+        config: &RuslicConfig,
         pure_fns: &'a PureFnMap<'tcx>,
         sig: RuslikFnSig<'tcx>,
         map: &mut PredMap,
     ) -> Result<SignatureSuccess<'a, 'tcx>, Unsupported> {
-        Self::from_fn_sig_map(tcx, pure_fns, sig, map, true)
+        Self::from_fn_sig_map(tcx, config, pure_fns, sig, map, true)
     }
     pub fn from_fn_sig_map<'a, 'tcx>(
         tcx: TyCtxt<'tcx>,
+        config: &RuslicConfig,
         pure_fns: &'a PureFnMap<'tcx>,
         sig: RuslikFnSig<'tcx>,
         map: &mut PredMap,
         in_main: bool,
     ) -> Result<SignatureSuccess<'a, 'tcx>, Unsupported> {
-        let use_full_names = std::env::var("RUSLIC_USE_FULL_NAMES")
-            .map(|v| v.parse::<bool>().unwrap())
-            .unwrap_or(false);
-        let optimistically_allow_private_types =
-            std::env::var("RUSLIC_OPTIMISTICALLY_ALLOW_PRIVATE_TYPES")
-                .map(|v| v.parse::<bool>().unwrap())
-                .unwrap_or(false);
         let mut stt = STyTranslator {
-            use_full_names,
-            optimistically_allow_private_types,
+            use_full_names: config.use_full_names,
+            optimistically_allow_private_types: config.optimistically_allow_private_types,
             tcx,
             map,
             tys: FxHashSet::default(),
@@ -693,10 +818,7 @@ impl Signature {
         };
         if sig.args.iter().any(|(v, _)| v.uuid().is_empty()) {
             // functions with args (_: i32, Struct { f }: Struct) not supported
-            return Err(Unsupported {
-                in_main,
-                reason: Reason::UnnamedArgs,
-            });
+            return Err(Unsupported::new(in_main, Reason::UnnamedArgs));
         }
         let sigma = Sigma(
             sig.args
@@ -704,9 +826,9 @@ impl Signature {
                 .enumerate()
                 .map(|(_card, (v, ty))|
             // The leading `f` is cut off in suslik:
-            stt.translate_sapp(false, &v.rname(), *ty))
+            stt.translate_sapp(false, &v.rname(), *ty).map_err(|err| err.within(&v.rname())))
                 .collect::<Result<_, _>>()
-                .map_err(|reason| Unsupported { in_main, reason })?,
+                .map_err(|err| Unsupported::from_ty_error(tcx, in_main, sig.def_id, err))?,
         );
         let mut pre = Assertion {
             phi: Phi::empty(),
@@ -723,15 +845,24 @@ impl Signature {
             is_fn_body: false,
             under_cond: Vec::new(),
             used_pure_fns: &mut used_pure_fns,
+            splits: SeqSplits::new(in_main),
         };
         let expr = et.translate_expr(&sig.pure_pre, Vec::new(), None);
+        if let Some(what) = expr.unsupported() {
+            return Err(Unsupported::from_spec(tcx, in_main, sig.def_id, what));
+        }
+        let splits = std::mem::take(&mut et.splits.constraints);
         pre.phi.0.extend(expr.flatten());
+        pre.phi.0.extend(splits);
 
         // TODO: remove special treatment of unit
         let result = if !sig.ret.is_unit() {
             Some(
                 stt.translate_sapp(false, "result", sig.ret)
-                    .map_err(|reason| Unsupported { in_main, reason })?,
+                    .map_err(|err| {
+                        let err = err.within("result");
+                        Unsupported::from_ty_error(tcx, in_main, sig.def_id, err)
+                    })?,
             )
         } else {
             None
@@ -758,16 +889,26 @@ impl Signature {
             is_fn_body: false,
             under_cond: Vec::new(),
             used_pure_fns: &mut used_pure_fns,
+            splits: SeqSplits::new(!in_main),
         };
 
         // let lfts = et.translate_lfts();
 
         let expr = et.translate_expr(&sig.pure_post, Vec::new(), None);
+        if let Some(what) = expr.unsupported() {
+            return Err(Unsupported::from_spec(tcx, in_main, sig.def_id, what));
+        }
+        let splits = std::mem::take(&mut et.splits.constraints);
         post.phi.0.extend(expr.flatten());
+        post.phi.0.extend(splits);
 
         // pre.phi.0.extend(lfts.flatten());
-        let mut fn_name = tcx.item_name(sig.def_id).to_string();
-        if !in_main
+        let mut fn_name = tcx
+            .opt_item_name(sig.def_id)
+            .map_or_else(|| "closure".to_string(), |name| name.to_string());
+        if let Some(path) = crate::hir_translator::extern_path(tcx, sig.def_id) {
+            fn_name = path;
+        } else if !in_main
             && sig
                 .args
                 .first()
@@ -780,6 +921,9 @@ impl Signature {
                 let trait_name = tcx.def_path_str(parent);
                 let prefix = if trait_name.contains("::") && parent.is_local() { "crate::".to_string() } else { String::new() };
                 fn_name = prefix + trait_name.split('<').next().unwrap() + "::" + &fn_name;
+            } else if !sig.def_id.is_local() {
+                // Imported from another crate (see `crate_specs.rs`)
+                fn_name = crate::crate_specs::call_path(tcx, sig.def_id);
             }
         }
         let sig = Self {
@@ -812,7 +956,7 @@ impl Expr {
     pub fn update_vars<F: Fn(&mut String)>(&mut self, f: &F) {
         match self {
             Expr::Var(v) | Expr::Snap(_, v) | Expr::OnExpiry(_, _, v, _) => f(v),
-            Expr::Tuple(_, es) => {
+            Expr::Tuple(_, es) | Expr::Seq(es) => {
                 for e in es {
                     e.update_vars(f)
                 }
@@ -837,7 +981,7 @@ impl Expr {
                     *self = e_new.clone();
                 }
             }
-            Expr::Tuple(_, es) => {
+            Expr::Tuple(_, es) | Expr::Seq(es) => {
                 for e in es {
                     e.update_result(e_new)
                 }
@@ -864,7 +1008,7 @@ impl Expr {
             }
             Expr::Snap(_, _) => (),
             Expr::OnExpiry(_, _, _, _) => (),
-            Expr::Tuple(_, es) => {
+            Expr::Tuple(_, es) | Expr::Seq(es) => {
                 for e in es {
                     e.change_var(f)
                 }
@@ -882,78 +1026,64 @@ impl Expr {
             }
         }
     }
-    pub fn prim_to_invs<'tcx>(value: String, kind: &'tcx TyKind<'tcx>) -> Phi {
-        match *kind {
-            TyKind::Bool => Phi::empty(),
+    pub fn prim_to_invs<'tcx>(value: String, ty: Ty<'tcx>, tcx: TyCtxt<'tcx>) -> Phi {
+        let (min, max) = match *ty.kind() {
+            TyKind::Bool => return Phi::empty(),
             TyKind::Char => todo!(),
-            TyKind::Int(i) => {
-                let i = if matches!(i, rustc_middle::ty::IntTy::Isize) {
-                    rustc_middle::ty::IntTy::I64
-                } else {
-                    i
-                };
-                // TODO: Scala Ints cannot parse such big values
-                let (subtract, i) = match i {
-                    IntTy::I8 => (0, IntTy::I8),
-                    IntTy::I16 => (0, IntTy::I16),
-                    IntTy::I32 => (2, IntTy::I32),
-                    IntTy::I64 => (1, IntTy::I32),
-                    IntTy::I128 => (0, IntTy::I32),
-                    IntTy::Isize => unreachable!(),
-                };
-                let max_val = (1 << (i.bit_width().unwrap() - 1)) - 1 - subtract;
-                Phi(vec![
-                    Expr::BinOp(
-                        RustBinOp::Ge.into(),
-                        Box::new(Expr::Var(value.clone())),
-                        Box::new(Expr::UnOp(UnOp::Neg, Box::new(max_val.into()))),
-                    ),
-                    Expr::BinOp(
-                        RustBinOp::Le.into(),
-                        Box::new(Expr::Var(value)),
-                        Box::new(max_val.into()),
-                    ),
-                ])
-            }
-            TyKind::Uint(u) => {
-                let u = if matches!(u, rustc_middle::ty::UintTy::Usize) {
-                    rustc_middle::ty::UintTy::U64
-                } else {
-                    u
-                };
-                // TODO: Scala Ints cannot parse such big values
-                let (subtract, u) = match u {
-                    UintTy::U8 => (u128::MAX, UintTy::U8),
-                    UintTy::U16 => (u128::MAX, UintTy::U16),
-                    UintTy::U32 => (0, UintTy::U16),
-                    UintTy::U64 => (1, UintTy::U16),
-                    UintTy::U128 => (2, UintTy::U16),
-                    UintTy::Usize => unreachable!(),
-                };
-                let max_val = (u128::checked_shl(1, u.bit_width().unwrap() as u32))
-                    .unwrap_or(0)
-                    .wrapping_add(subtract)
-                    .into();
-                Phi(vec![
-                    Expr::BinOp(
-                        RustBinOp::Ge.into(),
-                        Box::new(Expr::Var(value.clone())),
-                        Box::new(0.into()),
-                    ),
-                    Expr::BinOp(RustBinOp::Le.into(), Box::new(Expr::Var(value)), Box::new(max_val)),
-                ])
+            TyKind::Int(_) | TyKind::Uint(_) => {
+                let (bits, signed) = int_width(tcx, ty).unwrap();
+                Self::int_range(bits, signed)
             }
             TyKind::Float(_) => todo!(),
-            TyKind::Tuple(t) if t.is_empty() => Phi::empty(),
+            TyKind::Tuple(t) if t.is_empty() => return Phi::empty(),
             _ => unreachable!(),
+        };
+        Phi(vec![
+            Expr::BinOp(
+                RustBinOp::Ge.into(),
+                Box::new(Expr::Var(value.clone())),
+                Box::new(min),
+            ),
+            Expr::BinOp(RustBinOp::Le.into(), Box::new(Expr::Var(value)), Box::new(max)),
+        ])
+    }
+    /// The smallest and largest value of a `bits` wide integer, e.g. `-128` and `127` for `i8`.
+    pub fn int_bounds(bits: u64, signed: bool) -> (i128, u128) {
+        assert!((1..=128).contains(&bits), "no {bits} bit integers");
+        if signed {
+            let max = u128::MAX >> (129 - bits);
+            (-(max as i128) - 1, max)
+        } else {
+            (0, u128::MAX >> (128 - bits))
         }
     }
+    /// `int_bounds` as expressions, e.g. `(- 128)` and `127` for `i8`.
+    pub fn int_range(bits: u64, signed: bool) -> (Self, Self) {
+        let (min, max) = Self::int_bounds(bits, signed);
+        let abs: Self = min.unsigned_abs().into();
+        let min = if min < 0 { Expr::UnOp(UnOp::Neg, Box::new(abs)) } else { abs };
+        (min, max.into())
+    }
     pub fn _eq(self, other: Self) -> Self {
         Self::BinOp(RustBinOp::Eq.into(), Box::new(self), Box::new(other))
     }
     pub fn is_true(&self) -> bool {
         matches!(self, Expr::Lit(Lit::Bool(true)))
     }
+    /// The first `Lit::Unsupported` within the expression, if any.
+    pub fn unsupported(&self) -> Option<&str> {
+        match self {
+            Expr::Lit(Lit::Unsupported(what)) => Some(what),
+            Expr::Var(_) | Expr::Snap(_, _) | Expr::OnExpiry(_, _, _, _) | Expr::Lit(_) => None,
+            Expr::Tuple(_, es) | Expr::Seq(es) => es.iter().find_map(Expr::unsupported),
+            Expr::BinOp(_, l, r) => l.unsupported().or_else(|| r.unsupported()),
+            Expr::UnOp(_, e) => e.unsupported(),
+            Expr::IfElse(b, t, e) => b
+                .unsupported()
+                .or_else(|| t.unsupported())
+                .or_else(|| e.unsupported()),
+        }
+    }
 }
 impl From<u128> for Expr {
     fn from(u: u128) -> Self {
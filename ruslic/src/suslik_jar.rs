@@ -0,0 +1,104 @@
+//! Locating the SuSLik jar and the `java` used to run it. This module only depends on `std`,
+//! since it is shared with the `cargo-russol` binary (which does not link against rustc).
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+pub const JAR_NAME: &str = "suslik.jar";
+/// SuSLik is built and tested against Java 8.
+pub const MIN_JAVA_VERSION: u32 = 8;
+
+#[derive(Debug, Clone)]
+pub struct SuslikJar {
+    pub jar: PathBuf,
+    /// Temporary `.syn` files (and traces) are written into subdirectories of this
+    pub work_dir: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct MissingJar {
+    pub searched: Vec<PathBuf>,
+}
+impl fmt::Display for MissingJar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "could not find the SuSLik jar, looked at:")?;
+        for path in &self.searched {
+            write!(f, "\n  {}", path.to_string_lossy())?;
+        }
+        Ok(())
+    }
+}
+
+impl SuslikJar {
+    /// Looks for the jar in order:
+    /// 1. The `SUSLIK_JAR` environment variable.
+    /// 2. The `suslik-jar` config option.
+    /// 3. `target/scala-2.12/suslik.jar` in a SuSLik checkout given by `SUSLIK_DIR`.
+    /// 4. Next to the `ruslic` binary (where `cargo russol setup` puts it).
+    ///
+    /// Never tries to build the jar, see `cargo russol setup` for that.
+    pub fn find(configured: Option<&Path>) -> Result<Self, MissingJar> {
+        let suslik_dir = std::env::var_os("SUSLIK_DIR").map(PathBuf::from);
+        let mut searched = Vec::new();
+        if let Some(jar) = std::env::var_os("SUSLIK_JAR") {
+            searched.push(PathBuf::from(jar));
+        }
+        searched.extend(configured.map(Path::to_path_buf));
+        if let Some(suslik_dir) = &suslik_dir {
+            searched.push(assembly_jar(suslik_dir));
+        }
+        searched.push(exe_dir().join(JAR_NAME));
+        match searched.iter().find(|jar| jar.is_file()) {
+            Some(jar) => {
+                let work_dir = suslik_dir.unwrap_or_else(|| jar.parent().unwrap().to_path_buf());
+                Ok(Self {
+                    jar: jar.clone(),
+                    work_dir,
+                })
+            }
+            None => Err(MissingJar { searched }),
+        }
+    }
+}
+
+/// The location `sbt assembly` builds the jar to within a SuSLik checkout.
+pub fn assembly_jar(suslik_dir: &Path) -> PathBuf {
+    suslik_dir.join("target").join("scala-2.12").join(JAR_NAME)
+}
+
+/// The directory containing the `ruslic` binary. When running as a test the
+/// executable is in `target/*/deps`, so back out once more.
+pub fn exe_dir() -> PathBuf {
+    let current_exe = std::env::current_exe().unwrap();
+    let current_dir = current_exe.parent().unwrap();
+    if current_dir.ends_with("deps") {
+        current_dir.parent().unwrap().to_path_buf()
+    } else {
+        current_dir.to_path_buf()
+    }
+}
+
+/// Returns the major version of the `java` on the path, e.g. `8` for `1.8.0_352` and `17` for `17.0.5`.
+pub fn java_version() -> Result<u32, String> {
+    let output = Command::new("java")
+        .arg("-version")
+        .output()
+        .map_err(|e| format!("failed to run `java -version`: {e}"))?;
+    // `java -version` prints to stderr, e.g. `openjdk version "17.0.5" 2022-10-18`
+    let text = String::from_utf8_lossy(&output.stderr);
+    let version = text
+        .split('"')
+        .nth(1)
+        .ok_or_else(|| format!("could not parse the output of `java -version`:\n{text}"))?;
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let major = match parts.next() {
+        Some("1") => parts.next(),
+        other => other,
+    };
+    major
+        .and_then(|major| major.parse().ok())
+        .ok_or_else(|| format!("could not parse java version \"{version}\""))
+}
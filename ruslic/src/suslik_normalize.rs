@@ -318,7 +318,7 @@ impl<'a> SnapPatcher<'a> {
                 }
             }
             Expr::OnExpiry(..) => unreachable!("{}", e),
-            Expr::Tuple(_, es) => {
+            Expr::Tuple(_, es) | Expr::Seq(es) => {
                 for e in es {
                     self.patch_snap(e)
                 }
@@ -486,8 +486,16 @@ impl fmt::Display for Expr {
                     .intersperse(", ".to_string())
                     .collect::<String>()
             ),
+            Expr::Seq(es) => write!(
+                f,
+                "[{}]",
+                es.iter()
+                    .map(|e| e.to_string())
+                    .intersperse(", ".to_string())
+                    .collect::<String>()
+            ),
             // We don't care about `LitIntType` since we know the exact type anyway
-            Expr::Lit(Lit::Int(i, _)) => write!(f, "{}", i),
+            Expr::Lit(Lit::Int(i, _)) => write_int(f, *i),
             Expr::Lit(Lit::Bool(b)) => write!(f, "{}", b),
             Expr::Lit(l) => panic!("Unsupported lit {:?}", l),
             Expr::BinOp(BinOp::Rust(op), box l, box r) => {
@@ -500,6 +508,16 @@ impl fmt::Display for Expr {
         }
     }
 }
+/// SuSLik parses integer literals as Scala `Int`s, so larger ones are written out in base
+/// `2^16`, which the SMT solver evaluates exactly: e.g. `u32::MAX` is `((65535 * 65536) + 65535)`.
+fn write_int(f: &mut fmt::Formatter, i: u128) -> fmt::Result {
+    if i <= i32::MAX as u128 {
+        return write!(f, "{}", i);
+    }
+    write!(f, "((")?;
+    write_int(f, i >> 16)?;
+    write!(f, " * 65536) + {})", i & 0xffff)
+}
 impl fmt::Display for Predicate {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.is_private {
@@ -559,6 +577,7 @@ impl fmt::Display for PredParameter {
             // Lifetime name manipulation here:
             FnSpecKind::Lft => write!(f, "lft &{}", &self.name[1..]),
             FnSpecKind::Set => write!(f, "set {}", self.name),
+            FnSpecKind::Seq => write!(f, "intseq {}", self.name),
         }
     }
 }
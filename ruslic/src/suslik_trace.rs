@@ -0,0 +1,250 @@
+//! Explanations of why SuSLik failed to synthesize a fn, read from the `trace.json` it writes
+//! with `output-trace`. The trace is a sequence of nodes of the search tree; the deepest goal
+//! reached is usually the closest SuSLik got to a solution, and what remained of it is what the
+//! spec could not provide.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use rustc_data_structures::fx::FxHashMap;
+use serde_json::Value;
+
+use crate::suslik::SuslikProgram;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct Explanation {
+    /// Depth in the search tree of the deepest goal reached
+    pub depth: usize,
+    /// The heaplets in the pre and post of that goal, prefixed by the Rust place they stand for
+    pub pre: Vec<String>,
+    pub post: Vec<String>,
+    /// The pure formulas of the post which are not (syntactically) in the pre
+    pub obligations: Vec<String>,
+    /// Where the trace is kept
+    pub trace: PathBuf,
+}
+
+impl Explanation {
+    /// `None` if there is no trace or it contains no goals. A trace cut short by a timeout is
+    /// read up to the last complete node. Goals whose assertions are in neither of the formats
+    /// SuSLik writes (see `assertion`) are skipped with a warning, rather than explaining nothing.
+    pub fn from_trace(trace: &Path, names: &FxHashMap<String, String>) -> Option<Self> {
+        let src = std::fs::read_to_string(trace).ok()?;
+        let goals: Vec<_> = nodes(&src)
+            .into_iter()
+            .filter(|node| node["goal"].is_object())
+            .collect();
+        let (depth, pre, post) = goals
+            .iter()
+            .filter_map(|node| {
+                let depth = node["id"].as_array().map_or(0, Vec::len);
+                let goal = &node["goal"];
+                Some((depth, assertion(&goal["pre"])?, assertion(&goal["post"])?))
+            })
+            // The last of the deepest, as later goals have had more rules applied
+            .fold(None, |deepest: Option<(usize, _, _)>, goal| match deepest {
+                Some((d, ..)) if d > goal.0 => deepest,
+                _ => Some(goal),
+            })
+            .or_else(|| {
+                if !goals.is_empty() {
+                    eprintln!(
+                        "warning: the goals in {} are not in a known format, cannot explain the failure",
+                        trace.display()
+                    );
+                }
+                None
+            })?;
+        let heaplets = |sigma: Vec<String>| {
+            sigma
+                .into_iter()
+                .map(|app| name_heaplet(app, names))
+                .collect()
+        };
+        Some(Self {
+            depth,
+            obligations: post
+                .phi
+                .into_iter()
+                .filter(|phi| !pre.phi.contains(phi) && *phi != "true")
+                .collect(),
+            pre: heaplets(pre.sigma),
+            post: heaplets(post.sigma),
+            trace: trace.to_path_buf(),
+        })
+    }
+}
+
+/// The pure and spatial parts of a pre or postcondition in the trace.
+struct Assertion {
+    phi: Vec<String>,
+    sigma: Vec<String>,
+}
+
+/// Reads an assertion of a `GoalEntry`, which SuSLik writes either as lists of the `pure`
+/// conjuncts and `spatial` heaplets, or (in older versions) pretty-printed as `{phi ; sigma}`.
+fn assertion(value: &Value) -> Option<Assertion> {
+    match value {
+        Value::String(pp) => {
+            let pp = pp.trim().strip_prefix('{')?.strip_suffix('}')?;
+            let (phi, sigma) = match split_top(pp, ";")[..] {
+                [phi, sigma] => (phi, sigma),
+                [sigma] => ("true", sigma),
+                _ => return None,
+            };
+            Some(Assertion {
+                phi: formulas(split_top(phi, "&&")),
+                sigma: formulas(split_top(sigma, "**")),
+            })
+        }
+        _ => Some(Assertion {
+            phi: strings(&value["pure"])?,
+            sigma: strings(&value["spatial"])?,
+        }),
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |items: &[String]| {
+            if items.is_empty() {
+                "emp".to_string()
+            } else {
+                items.join(", ")
+            }
+        };
+        write!(f, "deepest goal reached (depth {}):", self.depth)?;
+        write!(f, "\n  have: {}", list(&self.pre))?;
+        write!(f, "\n  need: {}", list(&self.post))?;
+        if !self.obligations.is_empty() {
+            write!(f, "\n  could not prove: {}", self.obligations.join(" && "))?;
+        }
+        write!(f, "\n  (full trace in {})", self.trace.display())
+    }
+}
+
+impl SuslikProgram {
+    /// Which Rust place each `SApp::field_name` in the program stands for: arguments (and
+    /// `result`) of the fn being synthesized, or fields of the types in `pred_map`.
+    pub(crate) fn rust_names(&self) -> FxHashMap<String, String> {
+        let mut fields: FxHashMap<String, Vec<String>> = FxHashMap::default();
+        for pred in self.pred_map.values() {
+            for (vid, clause) in pred.clauses.iter().enumerate() {
+                let is_enum = clause
+                    .assn
+                    .sigma
+                    .0
+                    .iter()
+                    .any(|app| app.field_name == "fdisc");
+                let owner = match &clause.name {
+                    Some(name) if is_enum => name.trim_start_matches("::"),
+                    _ => &pred.clean_name,
+                };
+                for app in &clause.assn.sigma.0 {
+                    // See `STyTranslator::fd_name_to_sus`
+                    let field = match app.field_name.as_str() {
+                        "f_666" => "*",
+                        "fdisc" => "discriminant",
+                        name => match name.strip_prefix(&format!("f{vid}")) {
+                            Some(field) => field.trim_start_matches('_'),
+                            None => continue,
+                        },
+                    };
+                    fields
+                        .entry(app.field_name.clone())
+                        .or_default()
+                        .push(format!("{owner}.{field}"));
+                }
+            }
+        }
+        // The same field name is used in every type with such a field
+        let mut names: FxHashMap<_, _> = fields
+            .into_iter()
+            .map(|(field_name, mut owners)| {
+                owners.sort();
+                owners.dedup();
+                (field_name, owners.join(" or "))
+            })
+            .collect();
+        let sig = &self.synth_fn;
+        for app in sig.pre.sigma.0.iter().chain(&sig.post.sigma.0) {
+            names.insert(app.field_name.clone(), app.field_name[1..].to_string());
+        }
+        names
+    }
+}
+
+/// The entries of the trace: SuSLik writes one json object per node or status update,
+/// separated by blank lines.
+fn nodes(src: &str) -> Vec<Value> {
+    let src = src.trim().trim_start_matches('[').trim_end_matches(']');
+    let mut nodes = Vec::new();
+    let mut rest = src;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<Value>();
+        match stream.next() {
+            Some(Ok(node)) => nodes.push(node),
+            _ => return nodes,
+        }
+        rest = &rest[stream.byte_offset()..];
+    }
+}
+
+/// A list of formulas.
+fn strings(value: &Value) -> Option<Vec<String>> {
+    let items = value.as_array()?.iter().filter_map(Value::as_str);
+    Some(formulas(items.collect()))
+}
+
+fn formulas(items: Vec<&str>) -> Vec<String> {
+    items
+        .into_iter()
+        .map(str::trim)
+        .filter(|s| !s.is_empty() && *s != "emp")
+        .map(String::from)
+        .collect()
+}
+
+/// Splits `s` on the occurrences of `sep` which are not nested within brackets, e.g. the
+/// conjuncts of `(a && b) && c` are `(a && b)` and `c`.
+fn split_top<'a>(s: &'a str, sep: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0_usize, 0);
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && i >= start && s[i..].starts_with(sep) => {
+                parts.push(&s[start..i]);
+                start = i + sep.len();
+            }
+            _ => (),
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Prefix a heaplet `field: pred(..)` with the Rust place that `field` stands for.
+fn name_heaplet(app: String, names: &FxHashMap<String, String>) -> String {
+    let field = app
+        .trim_start_matches("priv ")
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .trim();
+    // SuSLik makes names fresh by appending a number
+    let mut candidate = field;
+    loop {
+        if let Some(name) = names.get(candidate) {
+            return format!("`{name}` {app}");
+        }
+        match candidate.strip_suffix(|c: char| c.is_ascii_digit()) {
+            Some(shorter) => candidate = shorter,
+            None => return app,
+        }
+    }
+}
@@ -1,20 +1,21 @@
 use std::collections::hash_map::Entry;
 
-use rustc_ast::Mutability;
+use rustc_ast::{LitKind, Mutability};
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_hir::def::CtorKind;
 use rustc_middle::ty::{
-    BoundRegionKind, FreeRegion, GenericArgKind, Region, RegionKind, Ty, TyCtxt, TyKind,
+    BoundRegionKind, DefIdTree, FreeRegion, GenericArgKind, Region, RegionKind, Ty, TyCtxt, TyKind,
 };
-use rustc_span::{def_id::DefId, Span};
+use rustc_span::{def_id::DefId, sym, Span, Symbol};
 
 use crate::{
     hir_translator::{PureFn, PureFnMap},
+    int_ops::int_width,
     ruslik_pure::{BuiltinCallKind, CallInfo, PureExpression, UnOpKind},
     ruslik_types::{self, RuslikFnSig},
     subst_generics::{self, TyFoldable},
     suslik::{
-        Assertion, BinOp, BorrowInfo, Clause, Expr, FnSpecKind, Phi, PredArgument, PredMap,
+        Assertion, BinOp, BorrowInfo, Clause, Expr, FnSpecKind, Lit, Phi, PredArgument, PredMap,
         PredParameter, Predicate, Reason, RustBinOp, SApp, STy, Sigma,
     },
 };
@@ -28,13 +29,38 @@ pub struct STyTranslator<'a, 'tcx> {
     pub fn_id: DefId,
 }
 
+/// Why a type could not be translated.
+#[derive(Debug)]
+pub struct TyError {
+    pub reason: Reason,
+    /// The innermost type which is unsupported (without references or regions)
+    pub ty: Option<String>,
+    /// The fields through which it was reached
+    pub path: Vec<String>,
+}
+impl From<Reason> for TyError {
+    fn from(reason: Reason) -> Self {
+        Self {
+            reason,
+            ty: None,
+            path: Vec::new(),
+        }
+    }
+}
+impl TyError {
+    pub fn within(mut self, field: &str) -> Self {
+        self.path.insert(0, field.to_string());
+        self
+    }
+}
+
 impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
     pub fn translate_sapp(
         &mut self,
         is_private: bool,
         field_name: &str,
         ty: Ty<'tcx>,
-    ) -> Result<SApp, Reason> {
+    ) -> Result<SApp, TyError> {
         let ty = self.translate_ty(ty)?;
         Ok(SApp {
             is_private,
@@ -43,7 +69,20 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
         })
     }
 
-    fn translate_ty(&mut self, ty: Ty<'tcx>) -> Result<STy, Reason> {
+    fn translate_ty(&mut self, ty: Ty<'tcx>) -> Result<STy, TyError> {
+        self.translate_ty_unchecked(ty).map_err(|mut err| {
+            if err.ty.is_none() {
+                let mut inner_ty = ty;
+                while let TyKind::Ref(_, ty, _) = inner_ty.kind() {
+                    inner_ty = *ty;
+                }
+                err.ty = Some(self.tcx.erase_regions(inner_ty).to_string());
+            }
+            err
+        })
+    }
+
+    fn translate_ty_unchecked(&mut self, ty: Ty<'tcx>) -> Result<STy, TyError> {
         let (is_brrw, inner_ty) = self.ty_to_brrw(ty)?;
         let is_copy = inner_ty
             .is_copy_modulo_regions(self.tcx.at(Span::default()), self.tcx.param_env(self.fn_id));
@@ -54,7 +93,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
             TyKind::Bool | TyKind::Int(_) | TyKind::Uint(_) => {
                 if let Entry::Vacant(v) = self.map.entry(pred.clone()) {
                     let param = PredParameter::val(FnSpecKind::prim_to_kind(inner_ty));
-                    let facts = Expr::prim_to_invs(param.name.clone(), inner_ty.kind());
+                    let facts = Expr::prim_to_invs(param.name.clone(), inner_ty, self.tcx);
                     let prim_arg = Some(param.name.clone());
                     v.insert(Predicate {
                         is_prim: true,
@@ -63,6 +102,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                         is_private: false,
                         ident: pred.clone(),
                         clean_name,
+                        int_width: int_width(self.tcx, inner_ty),
                         facts: Phi::empty(), // TODO: put facts here
                         fn_spec: vec![param],
                         clauses: vec![Clause {
@@ -92,6 +132,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                         is_private: false,
                         ident: pred.clone(),
                         clean_name,
+                        int_width: None,
                         facts: Phi::empty(),
                         fn_spec: Vec::new(),
                         clauses: vec![Clause {
@@ -117,7 +158,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                     || clean_name.starts_with("std::pin::Pin")
                 {
                     // Pin is unsupported since it requires the `unsafe_pin_internals` flag
-                    return Err(Reason::RequiresFlag);
+                    return Err(Reason::RequiresFlag.into());
                 }
                 let regions = subst.iter().flat_map(|tl_arg| {
                     tl_arg.walk().filter_map(|ga| match ga.unpack() {
@@ -151,6 +192,44 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                         assert!(lft_params.next().is_none(), "{}", e.get());
                         args
                     }
+                    Entry::Vacant(v)
+                        if self.tcx.is_diagnostic_item(sym::Vec, adt.did())
+                            || self.tcx.is_diagnostic_item(sym::String, adt.did())
+                            || is_cell(self.tcx, inner_ty)
+                            || shared_ptr_ty(self.tcx, inner_ty).is_some() =>
+                    {
+                        if let Some(contents_ty) = shared_ptr_ty(self.tcx, inner_ty)
+                            && is_interior_mut(self.tcx, contents_ty)
+                        {
+                            // The contents could change while the `Rc` is shared
+                            return Err(Reason::Other.into());
+                        }
+                        // `Vec`, `String`, `Cell`, `RefCell`, `Rc` and `Arc` are abstract, they
+                        // only get the `len` (and `elems`) or `contents` params when
+                        // specifications use them (see the `Len`, `VecElems` and `CellGet`
+                        // builtins, and `*rc`). An `Rc` isn't `Copy`, so it is shared by `clone`
+                        let mut fn_spec = lft_params.clone();
+                        fn_spec.push(PredParameter::default());
+                        v.insert(Predicate {
+                            is_prim: false,
+                            is_copy,
+                            is_drop: adt.has_dtor(self.tcx),
+                            is_private: false,
+                            ident: pred.clone(),
+                            clean_name,
+                            int_width: None,
+                            facts: Phi::empty(),
+                            fn_spec,
+                            clauses: Vec::new(),
+                        });
+                        lft_params
+                            .into_iter()
+                            .map(|p| PredArgument {
+                                name: p.name.clone(),
+                                target: p,
+                            })
+                            .collect()
+                    }
                     Entry::Vacant(v) => {
                         let mut fn_spec = lft_params.clone();
                         fn_spec.push(PredParameter::default());
@@ -164,7 +243,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                         let is_drop = adt.has_dtor(self.tcx) && !adt.is_box();
                         if is_private {
                             // private types not currently supported
-                            return Err(Reason::PrivateType);
+                            return Err(Reason::PrivateType.into());
                         }
                         if adt.is_variant_list_non_exhaustive()
                             || adt
@@ -173,7 +252,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                                 .any(|v| v.is_field_list_non_exhaustive())
                         {
                             // non_exhaustive types not currently supported
-                            return Err(Reason::NonExhaustive);
+                            return Err(Reason::NonExhaustive.into());
                         }
                         // TODO: redo this (temporary workaround for private modules)
                         // the issue is that there may be multiple ways to address a type, e.g.
@@ -184,7 +263,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                             || clean_name.contains("proto::peer::Dyn")
                             || clean_name.contains("codec::error::UserError")
                         {
-                            return Err(Reason::Other);
+                            return Err(Reason::Other.into());
                         }
                         v.insert(Predicate {
                             is_prim: false,
@@ -193,6 +272,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                             is_private,
                             ident: pred.clone(),
                             clean_name: clean_name.clone(),
+                            int_width: None,
                             facts: Phi::empty(),
                             fn_spec,
                             clauses: Vec::new(),
@@ -209,7 +289,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                                     sigma: Sigma(vec![SApp {
                                         is_private: false,
                                         field_name: "f_666".to_string(),
-                                        ty: self.translate_ty(ty)?,
+                                        ty: self.translate_ty(ty).map_err(|e| e.within("*"))?,
                                     }]),
                                 }
                                 .add_seq(0.into()),
@@ -244,6 +324,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                                     }
                                     let ty = fd.ty(self.tcx, subst);
                                     self.translate_sapp(is_private, &field_name, ty)
+                                        .map_err(|e| e.within(fd.name.as_str()))
                                 }).collect::<Result<Vec<_>, _>>()?;
                                 sigma.0.extend(fields);
                                 let item_name = if adt.did().is_local() || !self.use_full_names {
@@ -260,7 +341,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                                     name, prim_arg: None, selector, equalities: FxHashMap::default(),
                                     assn: Assertion { phi: Phi::empty(), sigma }.add_seq(dval),
                                 })
-                            }).collect::<Result<Vec<_>, Reason>>()?
+                            }).collect::<Result<Vec<_>, TyError>>()?
                         };
                         self.map.get_mut(&pred).unwrap().clauses = clauses;
                         lft_params
@@ -278,7 +359,9 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                     fn_spec: lft_args,
                 })
             }
-            TyKind::Param(_p) => {
+            // Trait objects are opaque like generics, their methods are offered as components
+            // (see `trait_bounds::find_trait_fns`)
+            TyKind::Param(_) | TyKind::Dynamic(_, _) => {
                 // let pred = p.name.as_str().to_string();
                 if !self.map.contains_key(&pred) {
                     self.map.insert(
@@ -290,6 +373,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                             is_private: false,
                             ident: pred.clone(),
                             clean_name,
+                            int_width: None,
                             facts: Phi::empty(),
                             fn_spec: vec![PredParameter::default()],
                             clauses: Vec::new(),
@@ -345,6 +429,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                             is_private: false,
                             ident: pred.clone(),
                             clean_name,
+                            int_width: None,
                             facts: Phi::empty(),
                             fn_spec,
                             clauses: Vec::with_capacity(1),
@@ -357,6 +442,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                                 let is_private = false;
                                 let field_name = Self::fd_name_to_sus(0, &idx.to_string());
                                 self.translate_sapp(is_private, &field_name, ty)
+                                    .map_err(|e| e.within(&idx.to_string()))
                             })
                             .collect::<Result<Vec<_>, _>>()?;
                         sigma.0.extend(fields);
@@ -388,22 +474,43 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
                     fn_spec: lft_args,
                 })
             }
-            TyKind::Char | TyKind::Float(_) => Err(Reason::CharFloat),
-            TyKind::Str | TyKind::Array(_, _) | TyKind::Slice(_) => Err(Reason::ArraySlice),
-            TyKind::Foreign(_) | TyKind::RawPtr(_) => Err(Reason::Unsafe),
+            TyKind::Char | TyKind::Float(_) => Err(Reason::CharFloat.into()),
+            TyKind::Str => {
+                // Abstract like `String`
+                if let Entry::Vacant(v) = self.map.entry(pred.clone()) {
+                    v.insert(Predicate {
+                        is_prim: false,
+                        is_copy,
+                        is_drop: false,
+                        is_private: false,
+                        ident: pred.clone(),
+                        clean_name,
+                        int_width: None,
+                        facts: Phi::empty(),
+                        fn_spec: vec![PredParameter::default()],
+                        clauses: Vec::new(),
+                    });
+                }
+                Ok(STy {
+                    is_brrw,
+                    pred,
+                    fn_spec: Vec::new(),
+                })
+            }
+            TyKind::Array(_, _) | TyKind::Slice(_) => Err(Reason::ArraySlice.into()),
+            TyKind::Foreign(_) | TyKind::RawPtr(_) => Err(Reason::Unsafe.into()),
             TyKind::FnDef(_, _)
             | TyKind::FnPtr(_)
             | TyKind::Closure(_, _)
             | TyKind::Generator(_, _, _)
-            | TyKind::GeneratorWitness(_) => Err(Reason::Closure),
-            TyKind::Dynamic(_, _)
-            | TyKind::Never
+            | TyKind::GeneratorWitness(_) => Err(Reason::Closure.into()),
+            TyKind::Never
             | TyKind::Projection(_)
             | TyKind::Opaque(_, _)
             | TyKind::Bound(_, _)
             | TyKind::Placeholder(_)
             | TyKind::Infer(_)
-            | TyKind::Error(_) => Err(Reason::OtherTy),
+            | TyKind::Error(_) => Err(Reason::OtherTy.into()),
             // _ => Err(format!("Reason ty {}", inner_ty)),
         }
     }
@@ -412,7 +519,15 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
         self.tys.insert(ty);
         let mut res = Vec::new();
         // let mut first_iter = true;
-        while let TyKind::Ref(r, new_ty, m) = *ty.kind() {
+        loop {
+            let (r, new_ty, m) = match *ty.kind() {
+                TyKind::Ref(r, new_ty, m) => (r, new_ty, m),
+                // `Ref` and `RefMut` guards are tracked like the references they hand out
+                _ if let Some(guard) = guard_ty(self.tcx, ty) => guard,
+                _ => break,
+            };
+            // Interior mutability: the contents can change even through a shared reference
+            let m = if is_interior_mut(self.tcx, new_ty) { Mutability::Mut } else { m };
             // Only top level
             // if first_iter && !r.is_static() {
             //     blockers.extend(self.possible_blocks.iter().filter(
@@ -427,6 +542,7 @@ impl<'a, 'tcx> STyTranslator<'a, 'tcx> {
             ty = new_ty;
             self.tys.insert(ty);
         }
+        self.tys.extend(refcell_guard_tys(self.tcx, ty));
         Ok((res, ty))
     }
 
@@ -454,6 +570,31 @@ pub struct ExprTranslator<'tcx, 'a, 'b> {
     pub under_cond: Vec<(bool, Expr)>,
 
     pub used_pure_fns: &'a mut Vec<&'b PureFn<'tcx>>,
+
+    /// The sequences split into fresh ghosts (see `split`), whose constraints are added to the
+    /// assertion which is being translated
+    pub splits: SeqSplits,
+}
+
+/// The splits of sequences into fresh ghosts, which encode `v[i]`, `take` and `skip` since SuSLik
+/// can't index into or split an `intseq`. Each sequence is split at most once at each index.
+#[derive(Default)]
+pub struct SeqSplits {
+    /// Whether a sequence may be split at a non-literal index. The length of the prefix can't be
+    /// tied to the index then, so the assertion is weakened: this is only sound in the
+    /// precondition of the synthesized fn and in the postconditions of the components.
+    pub loose: bool,
+    parts: FxHashMap<String, (Expr, Expr)>,
+    pub constraints: Vec<Expr>,
+}
+
+impl SeqSplits {
+    pub fn new(loose: bool) -> Self {
+        Self {
+            loose,
+            ..Self::default()
+        }
+    }
 }
 
 impl<'tcx, 'a, 'b> ExprTranslator<'tcx, 'a, 'b> {
@@ -537,7 +678,9 @@ impl<'tcx, 'a, 'b> ExprTranslator<'tcx, 'a, 'b> {
             }
             crate::ruslik_pure::ExprKind::Constructor(_, _, _) => todo!("{}", expr),
             crate::ruslik_pure::ExprKind::Field(box e, v, f) => {
-                if let TyKind::Ref(_, _, m) = e.ty().kind() {
+                if let TyKind::Ref(_, inner_ty, m) = e.ty().kind() {
+                    // `&Cell<T>` (or `&T` of any `T` with a `Cell` in it) is encoded like a `&mut`
+                    let m = if is_interior_mut(self.tcx, *inner_ty) { Mutability::Mut } else { *m };
                     let in_post = if (*f, *v) == ruslik_types::OLD {
                         false
                     } else if (*f, *v) == ruslik_types::FUT {
@@ -546,14 +689,24 @@ impl<'tcx, 'a, 'b> ExprTranslator<'tcx, 'a, 'b> {
                         unreachable!()
                     };
                     assert!(
-                        !in_post || *m == Mutability::Mut,
+                        !in_post || m == Mutability::Mut,
                         "It doesn't make sense to `^` immutable references, use `*` instead."
                     );
-                    if *m == Mutability::Mut {
+                    if m == Mutability::Mut {
                         futs.push(in_post)
                     };
                     return self.translate_expr(e, futs, param);
                 }
+                if let Some(inner_ty) = shared_ptr_ty(self.tcx, e.ty()) {
+                    // `*rc` is the `contents` of the abstract `Rc`, which can't be split up
+                    assert!((*f, *v) == ruslik_types::OLD, "Cannot use `^` on `Rc` or `Arc`.");
+                    if param.is_some() {
+                        let what = "part of the contents of an Rc".to_string();
+                        return Expr::Lit(Lit::Unsupported(what));
+                    }
+                    let param = contents_param(inner_ty);
+                    return self.translate_expr(e, futs, Some((param, true.into())));
+                }
                 // Defaults to structural value (capturing everything) field
                 let (mut param, facts) =
                     param.unwrap_or_else(|| (PredParameter::default(), true.into()));
@@ -568,10 +721,11 @@ impl<'tcx, 'a, 'b> ExprTranslator<'tcx, 'a, 'b> {
                     }
                     ty = *inner_ty;
                 }
+                let ty = peel_ptrs(self.tcx, ty);
                 // The `ty` of expr could be behind refs, but should have a corresponding amount of `futs`
                 // This can be false when were doing structural eq:
                 // assert_eq!(_mut_ref_count as usize, futs.len());
-                let pred = ty_to_pred_name(ty.peel_refs(), self.tcx);
+                let pred = ty_to_pred_name(ty, self.tcx);
                 if !self.map.contains_key(&pred) {
                     println!("Could not find pred {} in map {:?}", pred, self.map.keys());
                 }
@@ -599,7 +753,7 @@ impl<'tcx, 'a, 'b> ExprTranslator<'tcx, 'a, 'b> {
                 } else {
                     todo!("{:?}", expr.ty())
                 }
-                let pred = ty_to_pred_name(e.ty().peel_refs(), self.tcx);
+                let pred = ty_to_pred_name(peel_ptrs(self.tcx, e.ty()), self.tcx);
                 if !self.map.contains_key(&pred) {
                     println!("Could not find: {pred} in {:?}", self.map.keys());
                 }
@@ -710,10 +864,10 @@ impl<'tcx, 'a, 'b> ExprTranslator<'tcx, 'a, 'b> {
                     unreachable!()
                 };
 
-                if let TyKind::Adt(_adt, _) = fn_args[0].ty().peel_refs().kind() {
+                if let TyKind::Adt(_adt, _) = peel_ptrs(self.tcx, fn_args[0].ty()).kind() {
                     // let pred = self.tcx.item_name(adt.did());
                     // let pred = pred.as_str();
-                    let pred = ty_to_pred_name(fn_args[0].ty().peel_refs(), self.tcx);
+                    let pred = ty_to_pred_name(peel_ptrs(self.tcx, fn_args[0].ty()), self.tcx);
                     let param_name = self.tcx.item_name(*id).as_str().to_string();
                     assert!(arg_exprs.is_empty());
                     let param_name_result = param_name
@@ -767,7 +921,7 @@ impl<'tcx, 'a, 'b> ExprTranslator<'tcx, 'a, 'b> {
                                 field_name: format!("f{}", name.uuid()),
                                 ty: STy {
                                     is_brrw: Vec::new(),
-                                    pred: ty_to_pred_name(arg.ty().peel_refs(), self.tcx),
+                                    pred: ty_to_pred_name(peel_ptrs(self.tcx, arg.ty()), self.tcx),
                                     fn_spec: Vec::new(),
                                 },
                             })
@@ -809,10 +963,16 @@ impl<'tcx, 'a, 'b> ExprTranslator<'tcx, 'a, 'b> {
                         is_fn_body: true,
                         under_cond: Vec::new(),
                         used_pure_fns: &mut used_pure_fns,
+                        splits: SeqSplits::new(false),
                     };
 
                     let mut call_post =
                         translator.translate_expr(&pure_fn.pure_post, Vec::new(), None);
+                    // The ghosts of a split sequence can't be bound within a predicate
+                    let split_in_pure_fn = || Expr::Lit(Lit::Unsupported("v[k], take or skip in a pure fn".to_string()));
+                    if !translator.splits.constraints.is_empty() {
+                        call_post = split_in_pure_fn();
+                    }
                     // trusted_ensures:
                     if !call_post.is_true() {
                         // TODO: use this instead
@@ -846,6 +1006,9 @@ impl<'tcx, 'a, 'b> ExprTranslator<'tcx, 'a, 'b> {
 
                         // println!("Translating: {}", fn_body);
                         let mut body = translator.translate_expr(&pure_fn.expr, Vec::new(), None);
+                        if !translator.splits.constraints.is_empty() {
+                            body = split_in_pure_fn();
+                        }
                         // Remove "_self_old"
                         let param_names = call_params.iter().map(|param| &param.name);
                         let mut arg_map: FxHashMap<_, _> = call_params
@@ -924,6 +1087,195 @@ impl<'tcx, 'a, 'b> ExprTranslator<'tcx, 'a, 'b> {
                 let elem = self.translate_expr(&args[1], Vec::new(), None);
                 Expr::BinOp(BinOp::SetContains, Box::new(elem), Box::new(set))
             }
+            crate::ruslik_pure::ExprKind::Call(
+                CallInfo::Builtin(kind @ (BuiltinCallKind::Len | BuiltinCallKind::VecElems)),
+                args,
+            ) => {
+                assert!(param.is_none());
+                assert!(futs.is_empty());
+                let param = Self::vec_param(*kind);
+                self.translate_expr(&args[0], Vec::new(), Some((param, true.into())))
+            }
+            crate::ruslik_pure::ExprKind::Call(
+                CallInfo::Builtin(BuiltinCallKind::CellGet),
+                args,
+            ) => {
+                assert!(param.is_none());
+                assert!(futs.is_empty());
+                let contents_ty = match peel_ptrs(self.tcx, args[0].ty()).kind() {
+                    TyKind::Adt(_, substs) => substs.type_at(0),
+                    _ => unreachable!(),
+                };
+                let param = contents_param(contents_ty);
+                self.translate_expr(&args[0], Vec::new(), Some((param, true.into())))
+            }
+            crate::ruslik_pure::ExprKind::Call(
+                CallInfo::Builtin(BuiltinCallKind::StrEq),
+                args,
+            ) => {
+                // Equal strings have the same snapshot and length, literals are translated
+                // to their id (see `Lit::from`) and length
+                assert!(param.is_none());
+                let mut sides = args.iter().map(|arg| {
+                    if let crate::ruslik_pure::ExprKind::Lit(lit @ LitKind::Str(s, _)) = arg.kind() {
+                        (Expr::Lit(lit.into()), (s.as_str().len() as u128).into())
+                    } else {
+                        let len = Self::vec_param(BuiltinCallKind::Len);
+                        let snap = (PredParameter::default(), true.into());
+                        (
+                            self.translate_expr(arg, Vec::new(), Some(snap)),
+                            self.translate_expr(arg, Vec::new(), Some((len, true.into()))),
+                        )
+                    }
+                });
+                let ((snap_l, len_l), (snap_r, len_r)) = (sides.next().unwrap(), sides.next().unwrap());
+                snap_l._eq(snap_r) & len_l._eq(len_r)
+            }
+            crate::ruslik_pure::ExprKind::Call(
+                CallInfo::Builtin(BuiltinCallKind::VecIndex),
+                args,
+            ) => {
+                assert!(param.is_none());
+                let elems = Self::vec_param(BuiltinCallKind::VecElems);
+                let elems = self.translate_expr(&args[0], Vec::new(), Some((elems, true.into())));
+                let len = Self::vec_param(BuiltinCallKind::Len);
+                let len = self.translate_expr(&args[0], Vec::new(), Some((len, true.into())));
+                let i = self.translate_expr(&args[1], Vec::new(), None);
+                // `v[i]` is the head of `v.elems().skip(i)`
+                let rest_len = Expr::BinOp(RustBinOp::Sub.into(), Box::new(len.clone()), Box::new(i.clone()));
+                let split = self.split(elems, len, i).and_then(|(_, rest)| self.split(rest, rest_len, 1u128.into()));
+                match split {
+                    Some((Expr::Seq(mut head), _)) => head.pop().unwrap(),
+                    _ => Expr::Lit(Lit::Unsupported("v[i] with a non-literal index".to_string())),
+                }
+            }
+            crate::ruslik_pure::ExprKind::Call(
+                CallInfo::Builtin(BuiltinCallKind::SeqConstruct),
+                args,
+            ) => {
+                let elems = match args.first().unwrap().kind() {
+                    crate::ruslik_pure::ExprKind::Constructor(_, _, args) => args,
+                    _ => return Expr::Lit(Lit::Unsupported("seq! of a non-array".to_string())),
+                };
+                let elems = elems.iter().map(|arg| match arg.1.kind() {
+                    crate::ruslik_pure::ExprKind::Constructor(_, _, args) => {
+                        Some(self.translate_expr(&args.first().unwrap().1, Vec::new(), None))
+                    }
+                    _ => None,
+                });
+                match elems.collect() {
+                    Some(elems) => Expr::Seq(elems),
+                    None => {
+                        let what = "seq! element which isn't a reference".to_string();
+                        Expr::Lit(Lit::Unsupported(what))
+                    }
+                }
+            }
+            crate::ruslik_pure::ExprKind::Call(
+                CallInfo::Builtin(kind @ (BuiltinCallKind::SeqTake | BuiltinCallKind::SeqSkip)),
+                args,
+            ) => {
+                assert!(param.is_none());
+                let is_take = matches!(kind, BuiltinCallKind::SeqTake);
+                let n = self.translate_expr(&args[1], Vec::new(), None);
+                let split = match (self.translate_expr(&args[0], Vec::new(), None), n.clone()) {
+                    // Literal sequences are split directly
+                    (Expr::Seq(mut elems), Expr::Lit(Lit::Int(k, _))) => {
+                        let rest = elems.split_off((k as usize).min(elems.len()));
+                        Some((Expr::Seq(elems), Expr::Seq(rest)))
+                    }
+                    (seq, _) => self.seq_len(&args[0]).and_then(|len| self.split(seq, len, n)),
+                };
+                let (prefix, rest) = match split {
+                    Some(split) => split,
+                    None => {
+                        let what = if is_take { "Seq::take" } else { "Seq::skip" };
+                        let what = format!("{what} of a non-literal count or of a sequence of unknown length");
+                        return Expr::Lit(Lit::Unsupported(what));
+                    }
+                };
+                if is_take {
+                    prefix
+                } else {
+                    rest
+                }
+            }
+        }
+    }
+
+    /// Fresh ghosts `prefix` and `rest` with `seq == prefix + rest` whenever `seq` (of length
+    /// `len`) has at least `n` elements, and unconstrained otherwise. If `n` is a literal `k` then
+    /// `prefix` is `[g_0, ..., g_{k-1}]`, otherwise its length isn't tied to `n`, which is only
+    /// allowed where `self.splits` is `loose`.
+    fn split(&mut self, seq: Expr, len: Expr, n: Expr) -> Option<(Expr, Expr)> {
+        // Splitting at `a + k` splits at `a` first, so that e.g. `v[i]` and `skip(i + 1)` agree
+        if let Expr::BinOp(BinOp::Rust(RustBinOp::Add), a, k) = &n
+            && let (false, Expr::Lit(Lit::Int(..))) = (matches!(**a, Expr::Lit(_)), &**k)
+        {
+            let rest_len = Expr::BinOp(RustBinOp::Sub.into(), Box::new(len.clone()), a.clone());
+            let (prefix, rest) = self.split(seq, len, (**a).clone())?;
+            let (head, rest) = self.split(rest, rest_len, (**k).clone())?;
+            let prefix = Expr::BinOp(RustBinOp::Add.into(), Box::new(prefix), Box::new(head));
+            return Some((prefix, rest));
+        }
+        let key = format!("{seq} @ {n}");
+        if let Some(parts) = self.splits.parts.get(&key) {
+            return Some(parts.clone());
+        }
+        let prefix = match &n {
+            Expr::Lit(Lit::Int(k, _)) => Expr::Seq((0..*k).map(|_| fresh_ghost()).collect()),
+            _ if self.splits.loose => fresh_ghost(),
+            _ => return None,
+        };
+        let rest = fresh_ghost();
+        let parts = Expr::BinOp(RustBinOp::Add.into(), Box::new(prefix.clone()), Box::new(rest.clone()));
+        let short = Expr::BinOp(RustBinOp::Lt.into(), Box::new(len), Box::new(n));
+        let split = Expr::BinOp(RustBinOp::Or.into(), Box::new(short), Box::new(seq._eq(parts)));
+        self.splits.constraints.push(split);
+        self.splits.parts.insert(key, (prefix.clone(), rest.clone()));
+        Some((prefix, rest))
+    }
+
+    /// The length of a sequence in a spec, if it is known: of `v.elems()`, `seq![..]`, or
+    /// sums and splits of these.
+    fn seq_len(&mut self, seq: &PureExpression<'tcx>) -> Option<Expr> {
+        use crate::ruslik_pure::ExprKind;
+        let bin = |op: RustBinOp, l: Expr, r: Expr| Expr::BinOp(op.into(), Box::new(l), Box::new(r));
+        Some(match seq.kind() {
+            ExprKind::Call(CallInfo::Builtin(BuiltinCallKind::VecElems), args) => {
+                let len = Self::vec_param(BuiltinCallKind::Len);
+                self.translate_expr(&args[0], Vec::new(), Some((len, true.into())))
+            }
+            ExprKind::Call(CallInfo::Builtin(BuiltinCallKind::SeqConstruct), args) => match args[0].kind() {
+                ExprKind::Constructor(_, _, elems) => (elems.len() as u128).into(),
+                _ => return None,
+            },
+            ExprKind::Call(CallInfo::Builtin(kind @ (BuiltinCallKind::SeqTake | BuiltinCallKind::SeqSkip)), args) => {
+                let (len, k) = (self.seq_len(&args[0])?, self.translate_expr(&args[1], Vec::new(), None));
+                let short = bin(RustBinOp::Lt, len.clone(), k.clone());
+                match kind {
+                    BuiltinCallKind::SeqTake => Expr::IfElse(Box::new(short), Box::new(len), Box::new(k)),
+                    _ => Expr::IfElse(Box::new(short), Box::new(0u128.into()), Box::new(bin(RustBinOp::Sub, len, k))),
+                }
+            }
+            ExprKind::BinOp(RustBinOp::Add, l, r) => bin(RustBinOp::Add, self.seq_len(l)?, self.seq_len(r)?),
+            _ => return None,
+        })
+    }
+
+    /// The parameter of the abstract `Vec`, `String` or `str` predicate which `v.len()` or
+    /// `v.elems()` refers to.
+    fn vec_param(kind: BuiltinCallKind) -> PredParameter {
+        match kind {
+            BuiltinCallKind::Len => PredParameter {
+                kind: FnSpecKind::Int,
+                name: "len".to_string(),
+            },
+            BuiltinCallKind::VecElems => PredParameter {
+                kind: FnSpecKind::Seq,
+                name: "elems".to_string(),
+            },
+            _ => unreachable!(),
         }
     }
 
@@ -993,10 +1345,11 @@ fn region_to_name(r: Region) -> Result<String, Reason> {
             bound_region: BoundRegionKind::BrNamed(_, n),
             ..
         }) => Ok(n.to_string()),
+        // The environment of a closure, see `closure.rs`
         RegionKind::ReFree(FreeRegion {
             bound_region: BoundRegionKind::BrEnv,
             ..
-        }) => todo!(),
+        }) => Ok("'env".to_string()),
         RegionKind::ReStatic => Ok("'static".to_string()),
         RegionKind::ReVar(_) => todo!(),
         RegionKind::RePlaceholder(_) => todo!(),
@@ -1005,15 +1358,22 @@ fn region_to_name(r: Region) -> Result<String, Reason> {
 }
 
 // include_refs: should only be true for generics
-fn ty_to_pred_name(mut ty: Ty, tcx: TyCtxt) -> String {
+fn ty_to_pred_name<'tcx>(mut ty: Ty<'tcx>, tcx: TyCtxt<'tcx>) -> String {
     let mut prefix = String::new();
-    while let TyKind::Ref(_, inner_ty, m) = ty.kind() {
-        if *m == Mutability::Mut {
-            prefix += "Rmut"
+    loop {
+        if let TyKind::Ref(_, inner_ty, m) = ty.kind() {
+            if *m == Mutability::Mut {
+                prefix += "Rmut"
+            } else {
+                prefix += "R"
+            };
+            ty = *inner_ty;
+        } else if let Some((_, inner_ty, m)) = guard_ty(tcx, ty) {
+            prefix += if m == Mutability::Mut { "Gmut" } else { "G" };
+            ty = inner_ty;
         } else {
-            prefix += "R"
-        };
-        ty = *inner_ty;
+            break;
+        }
     }
     let pred = match ty.kind() {
         TyKind::Adt(adt, subst) => {
@@ -1035,7 +1395,121 @@ fn ty_to_pred_name(mut ty: Ty, tcx: TyCtxt) -> String {
 
 pub fn ty_is_primitive(ty: Ty) -> bool {
     ty.is_primitive()
-        || matches!(ty.to_string().as_str(), ty if ty.starts_with("russol_contracts::Set"))
+        || matches!(ty.to_string().as_str(), ty if ty.starts_with("russol_contracts::Set") || ty.starts_with("russol_contracts::Seq"))
+}
+
+/// The pointee of an `Rc` or `Arc`. These are abstract predicates whose `contents` is the
+/// snapshot of the pointee, read through `Deref` and shared by `clone`.
+pub(crate) fn shared_ptr_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    match ty.kind() {
+        TyKind::Adt(adt, substs)
+            if tcx.is_diagnostic_item(sym::Rc, adt.did())
+                || tcx.is_diagnostic_item(sym::Arc, adt.did()) =>
+        {
+            Some(substs.type_at(0))
+        }
+        _ => None,
+    }
+}
+
+/// The `contents` param of an abstract `Cell`, `RefCell`, `Rc` or `Arc` of `contents_ty`: the
+/// snapshot of the contents, or their value if they're primitive.
+fn contents_param(contents_ty: Ty) -> PredParameter {
+    let kind = if ty_is_primitive(contents_ty) {
+        FnSpecKind::prim_to_kind(contents_ty)
+    } else {
+        FnSpecKind::Snap
+    };
+    PredParameter {
+        kind,
+        name: "contents".to_string(),
+    }
+}
+
+/// The name of `ty` if it is one of the types of `core::cell`.
+fn cell_item<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Symbol> {
+    match ty.kind() {
+        TyKind::Adt(adt, _)
+            if tcx.crate_name(adt.did().krate) == sym::core
+                && tcx.def_path_str(tcx.parent(adt.did())).ends_with("::cell") =>
+        {
+            Some(tcx.item_name(adt.did()))
+        }
+        _ => None,
+    }
+}
+
+/// A fresh ghost variable, for the parts of a split sequence.
+fn fresh_ghost() -> Expr {
+    static GHOSTS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let id = GHOSTS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    Expr::Var(format!("seq_ghost{id}"))
+}
+
+/// Whether `ty` is a `Cell` or `RefCell`, which are abstract and can be mutated through a
+/// shared reference (so `&Cell<T>` is encoded like a `&mut Cell<T>`).
+pub(crate) fn is_cell<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+    matches!(cell_item(tcx, ty), Some(name) if matches!(name.as_str(), "Cell" | "RefCell"))
+}
+
+/// Whether `ty` isn't `Freeze`, i.e. has an `UnsafeCell` (e.g. in a `Cell` or `RefCell`) which
+/// isn't behind a pointer. Shared references to such types are encoded like `&mut` ones, since
+/// the contents can change through them. Type parameters are taken to be `Freeze`.
+pub(crate) fn is_interior_mut<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+    match ty.kind() {
+        TyKind::Adt(adt, _) if adt.is_unsafe_cell() => true,
+        TyKind::Adt(adt, substs) => adt.all_fields().any(|fd| is_interior_mut(tcx, fd.ty(tcx, substs))),
+        TyKind::Tuple(tys) => tys.iter().any(|ty| is_interior_mut(tcx, ty)),
+        TyKind::Array(ty, _) | TyKind::Slice(ty) => is_interior_mut(tcx, *ty),
+        _ => false,
+    }
+}
+
+/// The lifetime, target and mutability of a `Ref` or `RefMut` guard, which are encoded as a
+/// reference with the same lifetime.
+pub(crate) fn guard_ty<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    ty: Ty<'tcx>,
+) -> Option<(Region<'tcx>, Ty<'tcx>, Mutability)> {
+    let m = match cell_item(tcx, ty)?.as_str() {
+        "Ref" => Mutability::Not,
+        "RefMut" => Mutability::Mut,
+        _ => return None,
+    };
+    match ty.kind() {
+        TyKind::Adt(_, substs) => Some((substs.region_at(0), substs.type_at(1), m)),
+        _ => unreachable!(),
+    }
+}
+
+/// The `Ref` and `RefMut` guards of the `RefCell` `ty`, so that the `borrow` and `borrow_mut`
+/// components can be instantiated for it.
+fn refcell_guard_tys<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Vec<Ty<'tcx>> {
+    match ty.kind() {
+        TyKind::Adt(adt, substs)
+            if matches!(cell_item(tcx, ty), Some(name) if name.as_str() == "RefCell") =>
+        {
+            let substs = tcx.intern_substs(&[tcx.lifetimes.re_static.into(), substs[0]]);
+            tcx.module_children(tcx.parent(adt.did()))
+                .iter()
+                .filter(|child| matches!(child.ident.as_str(), "Ref" | "RefMut"))
+                .filter_map(|child| child.res.opt_def_id())
+                .map(|did| tcx.mk_adt(tcx.adt_def(did), substs))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// `ty` without any references or `RefCell` guards around it.
+fn peel_ptrs<'tcx>(tcx: TyCtxt<'tcx>, mut ty: Ty<'tcx>) -> Ty<'tcx> {
+    loop {
+        ty = ty.peel_refs();
+        match guard_ty(tcx, ty).map(|(_, ty, _)| ty) {
+            Some(inner_ty) => ty = inner_ty,
+            None => return ty,
+        }
+    }
 }
 
 fn extract_box_ty<'tcx>(ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
@@ -1097,8 +1571,10 @@ pub fn sanitize(s: &str) -> String {
             ']' => Some('_'),
             '(' => None,
             ')' => None,
-            // r#dyn
+            // r#dyn, or main::{closure#0}
             '#' => Some('_'),
+            '{' => None,
+            '}' => None,
             // impl Trait + 'static
             '+' => None,
             // Trait<Item = T>
@@ -1,15 +1,23 @@
 use rustc_data_structures::fx::FxHashSet;
 use rustc_hir::def_id::DefId;
 use rustc_middle::ty::{
-    AssocItems, AssocKind, EarlyBinder, GenericPredicates, PredicateKind, Subst, Ty, TyCtxt,
+    AssocItems, AssocKind, EarlyBinder, GenericPredicates, PredicateKind, Subst, TraitRef, Ty,
+    TyCtxt, TyKind,
 };
+use rustc_trait_selection::traits::{is_vtable_safe_method, supertraits};
 
-use crate::{ruslik_pure::PureExpression, ruslik_types::RuslikFnSig};
+use crate::{
+    hir_translator::TraitSpecMap,
+    ruslik_pure::PureExpression,
+    ruslik_types::RuslikFnSig,
+    subst_generics::{SubstFolder, TyFoldable},
+};
 
 pub(crate) fn find_trait_fns<'tcx>(
     tcx: TyCtxt<'tcx>,
     def_id: DefId,
     tys: &FxHashSet<Ty<'tcx>>,
+    trait_specs: &TraitSpecMap<'tcx>,
 ) -> Vec<RuslikFnSig<'tcx>> {
     let mut gp: Vec<GenericPredicates> = vec![tcx.predicates_of(def_id)];
     while let Some(parent) = gp.last().unwrap().parent {
@@ -30,35 +38,7 @@ pub(crate) fn find_trait_fns<'tcx>(
                         // eprintln!("Skipping predicate {t:?} since self {} isn't translated.", t.self_ty());
                         return Vec::new();
                     }
-                    let items: &AssocItems = tcx.associated_items(t.trait_ref.def_id);
-                    items
-                        .in_definition_order()
-                        .filter(|i| i.kind == AssocKind::Fn)
-                        .flat_map(|method| {
-                            // Ignore trait fns with generics
-                            if !tcx.generics_of(method.def_id).params.is_empty() {
-                                return None;
-                            }
-                            let pre = PureExpression::from_bool(true, tcx);
-                            let post = PureExpression::from_bool(true, tcx);
-                            let mut rfs =
-                                RuslikFnSig::new(method.def_id, tcx, pre, post, String::new(), 0);
-                            // Ignore fns with args like "(_: i32, Struct { f }: Struct)" since we don't support them yet
-                            if rfs.args.iter().any(|(v, _)| v.uuid().is_empty()) {
-                                return None;
-                            }
-                            rfs.args = rfs
-                                .args
-                                .into_iter()
-                                .map(|(arg, ty)| {
-                                    (arg, EarlyBinder(ty).subst(tcx, t.trait_ref.substs))
-                                })
-                                .collect();
-                            // println!("Substituting at {:?} with ty {} and gens {:?}", method.def_id, rfs.ret.ty, t.trait_ref.substs);
-                            rfs.ret = EarlyBinder(rfs.ret).subst(tcx, t.trait_ref.substs);
-                            Some(rfs)
-                        })
-                        .collect()
+                    trait_ref_fns(tcx, t.trait_ref, trait_specs, false)
                 }
                 PredicateKind::RegionOutlives(_) | PredicateKind::TypeOutlives(_) => Vec::new(),
                 PredicateKind::Projection(_)
@@ -75,5 +55,74 @@ pub(crate) fn find_trait_fns<'tcx>(
                 }
             }
         })
+        .chain(dyn_trait_fns(tcx, tys, trait_specs))
+        // A default trait fn sees itself through `Self: Trait`, calling it would never terminate
+        .filter(|sig| sig.def_id != def_id)
+        .collect()
+}
+
+/// The object-safe methods of the traits of the `dyn Trait` types in `tys` (including those of
+/// their supertraits), with `Self = dyn Trait`.
+fn dyn_trait_fns<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    tys: &FxHashSet<Ty<'tcx>>,
+    trait_specs: &TraitSpecMap<'tcx>,
+) -> Vec<RuslikFnSig<'tcx>> {
+    let mut seen = FxHashSet::default();
+    tys.iter()
+        .filter_map(|&ty| match ty.kind() {
+            TyKind::Dynamic(preds, _) if seen.insert(tcx.erase_regions(ty)) => {
+                Some(preds.principal()?.with_self_ty(tcx, ty))
+            }
+            _ => None,
+        })
+        .flat_map(|principal| supertraits(tcx, principal))
+        .filter_map(|trait_ref| trait_ref.no_bound_vars())
+        .flat_map(|trait_ref| trait_ref_fns(tcx, trait_ref, trait_specs, true))
+        .collect()
+}
+
+/// The methods of `trait_ref` without generics, with the trait's generics substituted (also in
+/// their specs, if the trait gives any).
+fn trait_ref_fns<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    trait_ref: TraitRef<'tcx>,
+    trait_specs: &TraitSpecMap<'tcx>,
+    object_safe: bool,
+) -> Vec<RuslikFnSig<'tcx>> {
+    let items: &AssocItems = tcx.associated_items(trait_ref.def_id);
+    items
+        .in_definition_order()
+        .filter(|i| i.kind == AssocKind::Fn && !i.name.as_str().starts_with("__russol_spec_"))
+        .filter(|i| !object_safe || is_vtable_safe_method(tcx, trait_ref.def_id, i))
+        .flat_map(|method| {
+            // Ignore trait fns with generics
+            if !tcx.generics_of(method.def_id).params.is_empty() {
+                return None;
+            }
+            let mut rfs = match trait_specs.get(&method.def_id) {
+                Some(spec) => spec.clone(),
+                None => {
+                    let pre = PureExpression::from_bool(true, tcx);
+                    let post = PureExpression::from_bool(true, tcx);
+                    RuslikFnSig::new(method.def_id, tcx, pre, post, String::new(), 0)
+                }
+            };
+            // Ignore fns with args like "(_: i32, Struct { f }: Struct)" since we don't support them yet
+            if rfs.args.iter().any(|(v, _)| v.uuid().is_empty()) {
+                return None;
+            }
+            rfs.args = rfs
+                .args
+                .into_iter()
+                .map(|(arg, ty)| (arg, EarlyBinder(ty).subst(tcx, trait_ref.substs)))
+                .collect();
+            // println!("Substituting at {:?} with ty {} and gens {:?}", method.def_id, rfs.ret.ty, trait_ref.substs);
+            rfs.ret = EarlyBinder(rfs.ret).subst(tcx, trait_ref.substs);
+            let mut folder = SubstFolder::from_substs_ref(tcx, trait_ref.substs);
+            rfs.pure_pre.subst(&mut folder);
+            rfs.pure_post.subst(&mut folder);
+            Some(rfs)
+        })
         .collect()
 }
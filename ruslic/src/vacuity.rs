@@ -0,0 +1,273 @@
+//! Checks that the spec of a fn is consistent before handing it to SuSLik. With an unsatisfiable
+//! precondition any body is a solution (SuSLik finds a trivial one), and with a postcondition
+//! that is unsatisfiable under it SuSLik searches until the timeout.
+//!
+//! Only the linear (in)equalities over integer variables in the pure parts are considered,
+//! anything else (e.g. sets, which share the same operators) is dropped, so a spec is only
+//! reported if it really is contradictory. Satisfiability of those is
+//! decided by Fourier-Motzkin elimination, tightened to integers.
+
+use std::collections::BTreeMap;
+
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+
+use crate::suslik::{
+    Assertion, BinOp, Expr, FnSpecKind, Lit, RustBinOp, SuslikProgram, UnOp, Vacuity,
+};
+
+/// Give up (and assume the spec is satisfiable) above this many constraints
+const MAX_CONSTRAINTS: usize = 2_000;
+
+impl SuslikProgram {
+    pub(crate) fn vacuity(&self) -> Option<Vacuity> {
+        let sig = &self.synth_fn;
+        let ints = sig
+            .pre
+            .sigma
+            .0
+            .iter()
+            .chain(&sig.post.sigma.0)
+            .flat_map(|app| &app.ty.fn_spec)
+            .filter(|arg| arg.target.kind == FnSpecKind::Int)
+            .map(|arg| arg.name.clone())
+            .collect();
+        let mut constraints = Vec::new();
+        self.assertion_constraints(&sig.pre, &ints, &mut constraints);
+        if !satisfiable(constraints.clone()) {
+            return Some(Vacuity::Pre);
+        }
+        self.assertion_constraints(&sig.post, &ints, &mut constraints);
+        (!satisfiable(constraints)).then_some(Vacuity::Post)
+    }
+
+    /// The linear constraints of `assn` over the `ints`, including the range of its primitive
+    /// heaplets.
+    fn assertion_constraints(
+        &self,
+        assn: &Assertion,
+        ints: &FxHashSet<String>,
+        out: &mut Vec<Linear>,
+    ) {
+        for expr in &assn.phi.0 {
+            constraints(expr, false, ints, out);
+        }
+        for app in &assn.sigma.0 {
+            let pred = match self.pred_map.get(&app.ty.pred) {
+                Some(pred) if pred.is_prim => pred,
+                _ => continue,
+            };
+            // The exact range of an integer type, rather than how the clause encodes it
+            if let Some((bits, signed)) = pred.int_width {
+                let (min, max) = Expr::int_bounds(bits, signed);
+                for arg in app.ty.fn_spec.iter().filter(|arg| ints.contains(&arg.name)) {
+                    let var = Linear::var(arg.name.clone());
+                    out.extend(Linear::constant(min).add(-1, &var));
+                    // Unbounded above if the maximum doesn't fit, which is still sound
+                    if let Ok(max) = i128::try_from(max) {
+                        out.extend(var.add(1, &Linear::constant(-max)));
+                    }
+                }
+                continue;
+            }
+            // The facts are about the predicate's parameters, e.g. `snap`
+            let args: FxHashMap<_, _> = pred
+                .fn_spec
+                .iter()
+                .zip(&app.ty.fn_spec)
+                .map(|(param, arg)| (param.name.as_str(), arg.name.as_str()))
+                .collect();
+            let facts = pred.clauses.iter().flat_map(|clause| &clause.assn.phi.0);
+            for fact in pred.facts.0.iter().chain(facts) {
+                constraints(&rename(fact, &args), false, ints, out);
+            }
+        }
+    }
+}
+
+fn rename(expr: &Expr, args: &FxHashMap<&str, &str>) -> Expr {
+    let rec = |e: &Expr| Box::new(rename(e, args));
+    match expr {
+        Expr::Var(v) => Expr::Var(args.get(v.as_str()).copied().unwrap_or(v).to_string()),
+        Expr::BinOp(op, l, r) => Expr::BinOp(*op, rec(l), rec(r)),
+        Expr::UnOp(op, e) => Expr::UnOp(*op, rec(e)),
+        Expr::IfElse(c, t, e) => Expr::IfElse(rec(c), rec(t), rec(e)),
+        Expr::Tuple(is_set, es) => {
+            Expr::Tuple(*is_set, es.iter().map(|e| rename(e, args)).collect())
+        }
+        Expr::Seq(es) => Expr::Seq(es.iter().map(|e| rename(e, args)).collect()),
+        other => other.clone(),
+    }
+}
+
+/// `sum(coeffs[x] * x) + constant <= 0`, or as a term just the sum.
+#[derive(Clone, Debug, Default)]
+struct Linear {
+    coeffs: BTreeMap<String, i128>,
+    constant: i128,
+}
+
+impl Linear {
+    fn constant(constant: i128) -> Self {
+        Self {
+            coeffs: BTreeMap::new(),
+            constant,
+        }
+    }
+    fn var(name: String) -> Self {
+        Self {
+            coeffs: [(name, 1)].into_iter().collect(),
+            constant: 0,
+        }
+    }
+    /// `self + factor * other`, `None` on overflow.
+    fn add(mut self, factor: i128, other: &Self) -> Option<Self> {
+        for (var, coeff) in &other.coeffs {
+            let sum = self.coeffs.entry(var.clone()).or_default();
+            *sum = sum.checked_add(coeff.checked_mul(factor)?)?;
+            if *sum == 0 {
+                self.coeffs.remove(var);
+            }
+        }
+        self.constant = self
+            .constant
+            .checked_add(other.constant.checked_mul(factor)?)?;
+        Some(self)
+    }
+    fn scale(&self, factor: i128) -> Option<Self> {
+        Self::constant(0).add(factor, self)
+    }
+    /// Divide a constraint by the gcd of its coefficients, which rounds the constant in the
+    /// direction that is valid for integers.
+    fn tighten(mut self) -> Self {
+        let gcd = self
+            .coeffs
+            .values()
+            .fold(0, |gcd, &c| num_gcd(gcd, c.abs()));
+        if gcd > 1 {
+            self.coeffs.values_mut().for_each(|c| *c /= gcd);
+            self.constant = -(-self.constant).div_euclid(gcd);
+        }
+        self
+    }
+}
+
+fn num_gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a
+    } else {
+        num_gcd(b, a % b)
+    }
+}
+
+/// The term `expr` if it is linear over the `ints`, with integer values of futures as opaque
+/// variables.
+fn linear(expr: &Expr, ints: &FxHashSet<String>) -> Option<Linear> {
+    match expr {
+        Expr::Var(v) if ints.contains(v) => Some(Linear::var(v.clone())),
+        Expr::OnExpiry(_, FnSpecKind::Int, ..) => Some(Linear::var(expr.to_string())),
+        Expr::Lit(Lit::Int(i, _)) => Some(Linear::constant((*i).try_into().ok()?)),
+        // Parentheses
+        Expr::Tuple(false, es) if es.len() == 1 => linear(&es[0], ints),
+        Expr::UnOp(UnOp::Neg, e) => linear(e, ints)?.scale(-1),
+        Expr::BinOp(BinOp::Rust(op), l, r) => {
+            let (l, r) = (linear(l, ints)?, linear(r, ints)?);
+            match op {
+                RustBinOp::Add => l.add(1, &r),
+                RustBinOp::Sub => l.add(-1, &r),
+                RustBinOp::Mul if l.coeffs.is_empty() => r.scale(l.constant),
+                RustBinOp::Mul if r.coeffs.is_empty() => l.scale(r.constant),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Add the constraints which `expr` (negated if `neg`) implies to `out`.
+fn constraints(expr: &Expr, neg: bool, ints: &FxHashSet<String>, out: &mut Vec<Linear>) {
+    match expr {
+        Expr::Lit(Lit::Bool(b)) if *b == neg => out.push(Linear::constant(1)),
+        Expr::Tuple(false, es) if es.len() == 1 => constraints(&es[0], neg, ints, out),
+        Expr::UnOp(UnOp::Not, e) => constraints(e, !neg, ints, out),
+        Expr::BinOp(BinOp::Rust(RustBinOp::And), l, r) if !neg => {
+            constraints(l, false, ints, out);
+            constraints(r, false, ints, out);
+        }
+        Expr::BinOp(BinOp::Rust(RustBinOp::Or), l, r) if neg => {
+            constraints(l, true, ints, out);
+            constraints(r, true, ints, out);
+        }
+        Expr::BinOp(BinOp::Rust(op), l, r) => {
+            let (l, r) = match (linear(l, ints), linear(r, ints)) {
+                (Some(l), Some(r)) => (l, r),
+                _ => return,
+            };
+            // Integers, so `a < b` is `a - b + 1 <= 0`
+            let le = |l: &Linear, r: &Linear, strict: bool| {
+                let mut c = l.clone().add(-1, r)?;
+                c.constant = c.constant.checked_add(strict as i128)?;
+                Some(c)
+            };
+            let implied = match (op, neg) {
+                (RustBinOp::Lt, false) | (RustBinOp::Ge, true) => vec![le(&l, &r, true)],
+                (RustBinOp::Le, false) | (RustBinOp::Gt, true) => vec![le(&l, &r, false)],
+                (RustBinOp::Gt, false) | (RustBinOp::Le, true) => vec![le(&r, &l, true)],
+                (RustBinOp::Ge, false) | (RustBinOp::Lt, true) => vec![le(&r, &l, false)],
+                (RustBinOp::Eq, false) | (RustBinOp::Ne, true) => {
+                    vec![le(&l, &r, false), le(&r, &l, false)]
+                }
+                _ => Vec::new(),
+            };
+            out.extend(implied.into_iter().flatten());
+        }
+        _ => (),
+    }
+}
+
+/// Whether the conjunction of `constraints` (each `<= 0`) has an integer solution. Only a
+/// `false` answer is exact.
+fn satisfiable(constraints: Vec<Linear>) -> bool {
+    let mut constraints: Vec<_> = constraints.into_iter().map(Linear::tighten).collect();
+    loop {
+        if constraints
+            .iter()
+            .any(|c| c.coeffs.is_empty() && c.constant > 0)
+        {
+            return false;
+        }
+        constraints.retain(|c| !c.coeffs.is_empty());
+        // Eliminate the variable which produces the fewest new constraints
+        let mut occurrences: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for c in &constraints {
+            for (var, coeff) in &c.coeffs {
+                let (pos, neg) = occurrences.entry(var).or_default();
+                *if *coeff > 0 { pos } else { neg } += 1;
+            }
+        }
+        let var = match occurrences
+            .into_iter()
+            .min_by_key(|(_, (pos, neg))| pos * neg)
+        {
+            Some((var, _)) => var.to_string(),
+            None => return true,
+        };
+        let (with, mut without): (Vec<_>, Vec<_>) = constraints
+            .into_iter()
+            .partition(|c| c.coeffs.contains_key(&var));
+        let (upper, lower): (Vec<_>, Vec<_>) = with.into_iter().partition(|c| c.coeffs[&var] > 0);
+        for u in &upper {
+            for l in &lower {
+                // Scale both so that `var` cancels out
+                let (cu, cl) = (u.coeffs[&var], -l.coeffs[&var]);
+                match u.scale(cl).and_then(|c| c.add(cu, l)) {
+                    Some(c) => without.push(c.tighten()),
+                    None => return true,
+                }
+            }
+        }
+        if without.len() > MAX_CONSTRAINTS {
+            return true;
+        }
+        constraints = without;
+    }
+}
@@ -0,0 +1,236 @@
+//! Specifications of commonly used std fns, injected into every crate as a module by `ruslic`
+//! (see `std_specs.rs`). Each wrapper is named after the fn it specifies, and solutions call the
+//! path given to `#[extern_spec]` directly. A crate can override an entry by declaring its own
+//! `#[extern_spec]` fn for the same path and self type, or drop it with `--skip-std-specs=<path>`.
+
+use ::russol_contracts::*;
+
+// Option
+
+#[extern_spec(Option::take)]
+#[ensures(match ^x { None => true, _ => false })]
+#[ensures(*x === result)]
+fn take<T>(x: &mut Option<T>) -> Option<T> { x.take() }
+
+#[extern_spec(Option::unwrap)]
+#[requires(matches!(x, Some(_)))]
+#[ensures(if let Some(v) = &x { v === result } else { false })]
+fn unwrap<T>(x: Option<T>) -> T { x.unwrap() }
+
+// Result
+
+#[extern_spec(Result::unwrap)]
+#[requires(matches!(x, Ok(_)))]
+#[ensures(if let Ok(v) = &x { v === result } else { false })]
+fn unwrap_ok<T, E: std::fmt::Debug>(x: Result<T, E>) -> T { x.unwrap() }
+
+#[extern_spec(Result::unwrap_err)]
+#[requires(matches!(x, Err(_)))]
+#[ensures(if let Err(e) = &x { e === result } else { false })]
+fn unwrap_err<T: std::fmt::Debug, E>(x: Result<T, E>) -> E { x.unwrap_err() }
+
+// Box
+
+#[extern_spec(Box::new)]
+#[ensures(*result === x)]
+fn box_new<T>(x: T) -> Box<T> { Box::new(x) }
+
+// Rc and Arc (abstract, shared with `clone` and read through `as_ref`)
+
+#[extern_spec(std::rc::Rc::new)]
+#[ensures(*result === x)]
+fn rc_new<T>(x: T) -> std::rc::Rc<T> { std::rc::Rc::new(x) }
+
+#[extern_spec(std::rc::Rc::clone)]
+#[ensures(*result === **x)]
+fn rc_clone<T>(x: &std::rc::Rc<T>) -> std::rc::Rc<T> { std::rc::Rc::clone(x) }
+
+#[extern_spec(std::rc::Rc::as_ref)]
+#[ensures(*result === **x)]
+fn rc_deref<T>(x: &std::rc::Rc<T>) -> &T { x }
+
+#[extern_spec(std::sync::Arc::new)]
+#[ensures(*result === x)]
+fn arc_new<T>(x: T) -> std::sync::Arc<T> { std::sync::Arc::new(x) }
+
+#[extern_spec(std::sync::Arc::clone)]
+#[ensures(*result === **x)]
+fn arc_clone<T>(x: &std::sync::Arc<T>) -> std::sync::Arc<T> { std::sync::Arc::clone(x) }
+
+#[extern_spec(std::sync::Arc::as_ref)]
+#[ensures(*result === **x)]
+fn arc_deref<T>(x: &std::sync::Arc<T>) -> &T { x }
+
+// Cell and RefCell (abstract, their contents can change through a shared reference)
+
+#[extern_spec(std::cell::Cell::new)]
+#[ensures(*result.contents() === x)]
+fn cell_new<T>(x: T) -> std::cell::Cell<T> { std::cell::Cell::new(x) }
+
+#[extern_spec(std::cell::Cell::get)]
+#[ensures(result === *c.contents())]
+#[ensures(*(^c).contents() === *c.contents())]
+fn cell_get<T: Copy>(c: &std::cell::Cell<T>) -> T { c.get() }
+
+#[extern_spec(std::cell::Cell::set)]
+#[ensures(*(^c).contents() === x)]
+fn cell_set<T>(c: &std::cell::Cell<T>, x: T) { c.set(x) }
+
+#[extern_spec(std::cell::Cell::replace)]
+#[ensures(result === *c.contents())]
+#[ensures(*(^c).contents() === x)]
+fn cell_replace<T>(c: &std::cell::Cell<T>, x: T) -> T { c.replace(x) }
+
+#[extern_spec(std::cell::Cell::take)]
+#[ensures(result === *c.contents())]
+fn cell_take<T: Default>(c: &std::cell::Cell<T>) -> T { c.take() }
+
+#[extern_spec(std::cell::Cell::into_inner)]
+#[ensures(result === *c.contents())]
+fn cell_into_inner<T>(c: std::cell::Cell<T>) -> T { c.into_inner() }
+
+#[extern_spec(std::cell::RefCell::new)]
+#[ensures(*result.contents() === x)]
+fn refcell_new<T>(x: T) -> std::cell::RefCell<T> { std::cell::RefCell::new(x) }
+
+#[extern_spec(std::cell::RefCell::borrow)]
+#[ensures(*result === *c.contents())]
+#[ensures(*(^c).contents() === *c.contents())]
+fn refcell_borrow<T>(c: &std::cell::RefCell<T>) -> std::cell::Ref<'_, T> { c.borrow() }
+
+#[extern_spec(std::cell::RefCell::borrow_mut)]
+#[ensures(*result === *c.contents())]
+#[ensures(*(^c).contents() === ^result)]
+fn refcell_borrow_mut<T>(c: &std::cell::RefCell<T>) -> std::cell::RefMut<'_, T> { c.borrow_mut() }
+
+#[extern_spec(std::cell::RefCell::replace)]
+#[ensures(result === *c.contents())]
+#[ensures(*(^c).contents() === x)]
+fn refcell_replace<T>(c: &std::cell::RefCell<T>, x: T) -> T { c.replace(x) }
+
+#[extern_spec(std::cell::RefCell::into_inner)]
+#[ensures(result === *c.contents())]
+fn refcell_into_inner<T>(c: std::cell::RefCell<T>) -> T { c.into_inner() }
+
+// std::mem
+
+#[extern_spec(std::mem::replace)]
+#[ensures(*dest === result)]
+#[ensures(^dest === src)]
+fn replace<T>(dest: &mut T, src: T) -> T { std::mem::replace(dest, src) }
+
+#[extern_spec(std::mem::swap)]
+#[ensures(^x === *y)]
+#[ensures(^y === *x)]
+fn swap<T>(x: &mut T, y: &mut T) { std::mem::swap(x, y) }
+
+#[extern_spec(std::mem::take)]
+#[ensures(*dest === result)]
+fn mem_take<T: Default>(dest: &mut T) -> T { std::mem::take(dest) }
+
+// Clone and Default
+
+// Assumes that `clone` returns an equal value, as derived impls do
+#[extern_spec(Clone::clone)]
+#[ensures(result === *x)]
+fn clone<T: Clone>(x: &T) -> T { x.clone() }
+
+#[extern_spec(Default::default)]
+fn default<T: Default>() -> T { T::default() }
+
+// Vec (an abstract predicate with a `len` and a sequence of `elems`)
+
+#[extern_spec(Vec::new)]
+#[ensures(result.len() == 0)]
+#[ensures(result.elems() == seq![])]
+fn vec_new<T>() -> Vec<T> { Vec::new() }
+
+#[extern_spec(Vec::len)]
+#[ensures(result == v.len())]
+fn vec_len<T>(v: &Vec<T>) -> usize { v.len() }
+
+#[extern_spec(Vec::push)]
+#[ensures((^v).len() == v.len() + 1)]
+#[ensures((^v).elems() == v.elems() + seq![&value])]
+fn vec_push<T>(v: &mut Vec<T>, value: T) { v.push(value) }
+
+#[extern_spec(Vec::pop)]
+#[ensures(match &result {
+    Some(x) => v.len() > 0 && (^v).len() == v.len() - 1 && v.elems() == (^v).elems() + seq![x],
+    None => v.len() == 0 && (^v).len() == 0,
+})]
+fn vec_pop<T>(v: &mut Vec<T>) -> Option<T> { v.pop() }
+
+// The elements at a non-literal index `i` are encoded with ghosts which aren't tied to `i`,
+// so the specs below say less than they could (but SuSLik can't split an `intseq` itself)
+
+#[extern_spec(<[_]>::get)]
+#[ensures(match result {
+    Some(x) => i < v.len() && v.elems().skip(i).take(1) == seq![x],
+    None => i >= v.len(),
+})]
+fn vec_get<T>(v: &Vec<T>, i: usize) -> Option<&T> { v.get(i) }
+
+#[extern_spec(<[_]>::get_mut)]
+#[ensures(match result {
+    Some(x) => i < v.len() && (^v).len() == v.len() && v.elems().skip(i).take(1) == seq![&*x],
+    None => i >= v.len() && (^v).len() == v.len() && (^v).elems() == v.elems(),
+})]
+fn vec_get_mut<T>(v: &mut Vec<T>, i: usize) -> Option<&mut T> { v.get_mut(i) }
+
+#[extern_spec(Vec::insert)]
+#[requires(i <= v.len())]
+#[ensures((^v).len() == v.len() + 1)]
+#[ensures((^v).elems() == v.elems().take(i) + seq![&x] + v.elems().skip(i))]
+fn vec_insert<T>(v: &mut Vec<T>, i: usize, x: T) { v.insert(i, x) }
+
+#[extern_spec(Vec::remove)]
+#[requires(i < v.len())]
+#[ensures((^v).len() == v.len() - 1)]
+#[ensures(v.elems() == (^v).elems().take(i) + seq![&result] + (^v).elems().skip(i))]
+fn vec_remove<T>(v: &mut Vec<T>, i: usize) -> T { v.remove(i) }
+
+#[extern_spec(<[_]>::swap)]
+#[requires(i < v.len() && j < v.len())]
+#[ensures((^v).len() == v.len())]
+#[ensures((^v).elems().skip(i).take(1) == v.elems().skip(j).take(1))]
+#[ensures((^v).elems().skip(j).take(1) == v.elems().skip(i).take(1))]
+fn vec_swap<T>(v: &mut Vec<T>, i: usize, j: usize) { v.swap(i, j) }
+
+// String and str (abstract predicates with a `len`, `==` compares the snapshots and lengths)
+
+#[extern_spec(String::new)]
+#[ensures(result == "")]
+fn string_new() -> String { String::new() }
+
+#[extern_spec(String::from)]
+#[ensures(result == *s)]
+fn string_from(s: &str) -> String { String::from(s) }
+
+#[extern_spec(String::push_str)]
+#[ensures((^s).len() == s.len() + t.len())]
+fn string_push_str(s: &mut String, t: &str) { s.push_str(t) }
+
+#[extern_spec(String::len)]
+#[ensures(result == s.len())]
+fn string_len(s: &String) -> usize { s.len() }
+
+#[extern_spec(str::len)]
+#[ensures(result == s.len())]
+fn str_len(s: &str) -> usize { s.len() }
+
+#[extern_spec(String::is_empty)]
+#[ensures(result == (s.len() == 0))]
+fn string_is_empty(s: &String) -> bool { s.is_empty() }
+
+#[extern_spec(str::is_empty)]
+#[ensures(result == (s.len() == 0))]
+fn str_is_empty(s: &str) -> bool { s.is_empty() }
+
+#[extern_spec(String::clone)]
+#[ensures(result == *s)]
+fn string_clone(s: &String) -> String { s.clone() }
+
+#[extern_spec(String::as_str)]
+#[ensures(*result == *s)]
+fn string_as_str(s: &String) -> &str { s.as_str() }
@@ -1,6 +1,9 @@
 use std::{fmt::Display, path::PathBuf};
 
-use ruslic::suslik::{MeanStats, Solved, SynthesisResult};
+use ruslic::{
+    config::RuslicConfig,
+    suslik::{MeanStats, Solved, SynthesisResult},
+};
 
 struct Category {
     dir: String,
@@ -9,7 +12,7 @@ struct Category {
     depth: u32,
 }
 impl Category {
-    fn run_tests_in_dir(dir: PathBuf, timeout: u64, depth: u32) -> Self {
+    fn run_tests_in_dir(dir: PathBuf, config: &RuslicConfig, depth: u32) -> Self {
         let mut cat = Self {
             dir: dir.file_name().unwrap().to_string_lossy().to_string(),
             results: Vec::new(),
@@ -36,7 +39,7 @@ impl Category {
                             "/name/of/binary".to_string(),
                             path.path().to_string_lossy().to_string(),
                         ],
-                        timeout,
+                        config,
                         false,
                     ) {
                         cat.results.extend(
@@ -52,7 +55,7 @@ impl Category {
                     }
                 }
             } else {
-                let results = Self::run_tests_in_dir(path.path(), timeout, depth + 1);
+                let results = Self::run_tests_in_dir(path.path(), config, depth + 1);
                 cat.children.push(results)
             }
         }
@@ -169,19 +172,24 @@ impl Display for Category {
 
 #[test]
 fn all_tests() {
-    let timeout = std::env::var("RUSLIC_TIMEOUT")
-        .ok()
-        .and_then(|t| t.parse().ok())
-        .unwrap_or(300_000);
+    let mut config = RuslicConfig {
+        timeout: 300_000,
+        // Most tests have a body, which is a solution to check against
+        synth_bodies: true,
+        ..Default::default()
+    };
+    config.apply_env().unwrap();
+    config.validate().unwrap();
+    let timeout = config.timeout;
     let is_eval = std::env::var("RUSLIC_EVAL")
         .ok()
         .map(|s| s.parse::<bool>().unwrap())
         .unwrap_or(false);
 
     let results = if is_eval {
-        all_tests_eval(timeout)
+        all_tests_eval(&config)
     } else {
-        Category::run_tests_in_dir(PathBuf::from("./tests/synth/"), timeout, 0)
+        Category::run_tests_in_dir(PathBuf::from("./tests/synth/"), &config, 0)
     };
     let max_ms = format_ms(results.max_ms());
     let results_str = format!("### Measured timings (max {max_ms}) ###{results}\n#######################################\n");
@@ -195,8 +203,8 @@ fn all_tests() {
     }
 }
 
-fn all_tests_eval(timeout: u64) -> Category {
-    Category::run_tests_in_dir(PathBuf::from("./tests/synth/paper"), timeout, 0)
+fn all_tests_eval(config: &RuslicConfig) -> Category {
+    Category::run_tests_in_dir(PathBuf::from("./tests/synth/paper"), config, 0)
 }
 
 fn format_ms(ms: u64) -> String {
@@ -0,0 +1,36 @@
+use ruslic::config::{Emit, RuslicConfig};
+
+// Like `ci.rs`, this runs SuSLik
+#[test]
+fn rebinds_captures() {
+    let emitted = std::env::temp_dir().join(format!("ruslic-closure-{}.json", std::process::id()));
+    let config = RuslicConfig {
+        subst_result: true,
+        emit: Emit::Json,
+        emit_path: Some(emitted.clone()),
+        ..Default::default()
+    };
+    let args = ["/name/of/binary", "tests/synth/other/closure/closure.rs"];
+    ruslic::run_on_file(
+        args.iter().map(|arg| arg.to_string()).collect(),
+        &config,
+        false,
+    )
+    .unwrap();
+    let edits = std::fs::read_to_string(&emitted).unwrap();
+    std::fs::remove_file(&emitted).unwrap();
+    let mut edits: Vec<serde_json::Value> = edits
+        .lines()
+        .map(|edit| serde_json::from_str(edit).unwrap())
+        .collect();
+    edits.sort_by_key(|edit| edit["range"]["start"].as_u64());
+    let bodies: Vec<_> = edits
+        .iter()
+        .map(|edit| edit["replacement"].as_str().unwrap())
+        .collect();
+    assert_eq!(bodies.len(), 4, "{bodies:?}");
+    assert!(bodies[0].contains("let v = &v;"), "{}", bodies[0]);
+    assert!(!bodies[1].contains("let b = "), "{}", bodies[1]);
+    assert!(bodies[2].contains("let v = &mut v;"), "{}", bodies[2]);
+    assert!(bodies[3].contains("let x = &mut *x;"), "{}", bodies[3]);
+}
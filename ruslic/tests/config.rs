@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+
+use ruslic::config::{Emit, RuslicConfig, CONFIG_ENV};
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn manifest(name: &str, metadata: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ruslic-config-{}-{name}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let manifest = dir.join("Cargo.toml");
+    let content = format!(
+        "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\n\n[package.metadata.russol]\n{metadata}"
+    );
+    std::fs::write(&manifest, content).unwrap();
+    manifest
+}
+
+#[test]
+fn parse_cli() {
+    let (options, rest) = RuslicConfig::split_cli(args(&[
+        "file.rs",
+        "--timeout",
+        "500",
+        "--summarise",
+        "--only=a::*,b",
+        "--emit=diff",
+        "--edition=2021",
+    ]))
+    .unwrap();
+    assert_eq!(rest, args(&["file.rs", "--edition=2021"]));
+    let mut config = RuslicConfig::default();
+    config.apply_options(&options).unwrap();
+    assert_eq!(config.timeout, 500);
+    assert!(config.summarise);
+    assert_eq!(config.only, args(&["a::*", "b"]));
+    assert_eq!(config.emit, Emit::Diff);
+
+    let missing = RuslicConfig::split_cli(args(&["--timeout"])).unwrap_err();
+    assert!(missing.to_string().contains("missing value for `--timeout`"));
+    let invalid = RuslicConfig::default()
+        .apply_cli(args(&["--thread-count=many"]))
+        .unwrap_err();
+    assert!(invalid.to_string().contains("`--thread-count`"));
+}
+
+#[test]
+fn cli_overrides_manifest() {
+    let manifest = manifest(
+        "overrides",
+        "timeout = 1000\nthread_count = 2\nskip = [\"a\", \"b\"]\n",
+    );
+    let (config, rest) = RuslicConfig::load(
+        RuslicConfig::default(),
+        Some(&manifest),
+        args(&["--timeout=50", "--skip", "c", "lib.rs"]),
+    )
+    .unwrap();
+    assert_eq!(rest, args(&["lib.rs"]));
+    assert_eq!(config.timeout, 50);
+    assert_eq!(config.thread_count, 2);
+    assert_eq!(config.skip, args(&["a", "b", "c"]));
+
+    let invalid = manifest_error("invalid", "timeout = \"soon\"\n");
+    assert!(invalid.contains("`timeout` in `[package.metadata.russol]`"));
+    let unknown = manifest_error("unknown", "not_an_option = true\n");
+    assert!(unknown.contains("unknown ruslic option `not-an-option`"));
+}
+
+fn manifest_error(name: &str, metadata: &str) -> String {
+    let manifest = manifest(name, metadata);
+    RuslicConfig::load(RuslicConfig::default(), Some(&manifest), Vec::new())
+        .unwrap_err()
+        .to_string()
+}
+
+#[test]
+fn each_crate_applies_its_manifest() {
+    let base = RuslicConfig {
+        summarise: true,
+        ..Default::default()
+    };
+    let (options, _) = RuslicConfig::split_cli(args(&["--timeout=50"])).unwrap();
+    std::env::set_var(CONFIG_ENV, base.to_env(&options));
+
+    let member = manifest("member", "timeout = 1000\nthread_count = 3\n");
+    let config = RuslicConfig::for_crate(Some(&member)).unwrap().unwrap();
+    assert!(config.summarise);
+    assert_eq!(config.timeout, 50);
+    assert_eq!(config.thread_count, 3);
+
+    let other = manifest("other", "check_vacuity = false\n");
+    let config = RuslicConfig::for_crate(Some(&other)).unwrap().unwrap();
+    assert_eq!(config.thread_count, base.thread_count);
+    assert!(!config.check_vacuity);
+
+    std::env::remove_var(CONFIG_ENV);
+    assert!(RuslicConfig::for_crate(Some(&member)).is_none());
+}
@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+
+use ruslic::{
+    config::RuslicConfig,
+    suslik::{SynthesisResultKind, Vacuity},
+    suslik_jar::exe_dir,
+};
+
+/// The `russol_contracts` args which `cargo russol` would pass for a crate depending on it.
+fn contracts() -> [String; 4] {
+    let rlib = exe_dir().join("librussol_contracts.rlib");
+    [
+        "--extern".to_string(),
+        format!("russol_contracts={}", rlib.to_string_lossy()),
+        "-L".to_string(),
+        format!("dependency={}", exe_dir().join("deps").to_string_lossy()),
+    ]
+}
+
+/// Compiles `tests/crate_specs/dep.rs` into `out` as under `cargo russol`.
+fn export_dep(out: &Path) {
+    let args = [
+        "/name/of/binary",
+        "tests/crate_specs/dep.rs",
+        "--crate-name=dep",
+        "--crate-type=lib",
+        "--edition=2021",
+        "--emit=metadata",
+        "--out-dir",
+        out.to_str().unwrap(),
+    ];
+    let args = args.iter().map(|arg| arg.to_string()).chain(contracts());
+    ruslic::export_specs(args.collect()).unwrap();
+}
+
+fn exported_specs(out: &Path) -> serde_json::Value {
+    let specs = std::fs::read_to_string(out.join("libdep.russol.json")).unwrap();
+    serde_json::from_str(&specs).unwrap()
+}
+
+/// A directory of its own, removed when dropped.
+struct TempDir(PathBuf);
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+// Both compilations run in one test, since the compiler has global state
+#[test]
+fn exports_and_imports_specs() {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let out =
+        std::env::temp_dir().join(format!("ruslic-crate-specs-{}-{nanos}", std::process::id()));
+    std::fs::create_dir_all(&out).unwrap();
+    let out = TempDir(out);
+    export_dep(&out.0);
+
+    let specs = exported_specs(&out.0);
+    // `double` (re-exported) and `add_two`, but not `inc`
+    assert_eq!(specs["fn_specs"].as_array().unwrap().len(), 2);
+    assert_eq!(specs["pure_fns"].as_array().unwrap().len(), 1);
+
+    let config = RuslicConfig {
+        synth_bodies: true,
+        only: vec!["never".to_string()],
+        ..Default::default()
+    };
+    let dep = format!("dep={}", out.0.join("libdep.rmeta").to_string_lossy());
+    let args = [
+        "/name/of/binary",
+        "tests/crate_specs/user.rs",
+        "--extern",
+        &dep,
+    ];
+    let res = ruslic::run_on_file(
+        args.iter().map(|arg| arg.to_string()).collect(),
+        &config,
+        false,
+    )
+    .unwrap();
+    assert!(
+        matches!(
+            res["never"].kind,
+            SynthesisResultKind::Vacuous(Vacuity::Pre)
+        ),
+        "{:?}",
+        res["never"]
+    );
+}
@@ -0,0 +1,37 @@
+use russol_contracts::*;
+
+mod imp {
+    use russol_contracts::*;
+
+    // Reachable through the re-export below
+    #[requires(x < 1000)]
+    #[ensures(result == x + x)]
+    pub fn double(x: u32) -> u32 {
+        x + x
+    }
+
+    // `pub`, but in a private module
+    #[requires(x < 1000)]
+    #[ensures(result == x + 1)]
+    pub fn inc(x: u32) -> u32 {
+        x + 1
+    }
+}
+pub use imp::double;
+
+#[requires(x < 1000)]
+#[ensures(result == x + 2)]
+pub fn add_two(x: u32) -> u32 {
+    imp::inc(imp::inc(x))
+}
+
+pub struct Meter {
+    pub reading: u32,
+}
+
+impl Meter {
+    #[pure]
+    pub fn in_range(&self) -> bool {
+        self.reading < 1000
+    }
+}
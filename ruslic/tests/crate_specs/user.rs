@@ -0,0 +1,12 @@
+use russol_contracts::*;
+
+// Only solvable with the specs of `dep`
+#[requires(x < 100)]
+#[ensures(result == x + x + 2)]
+fn double_add_two(x: u32) -> u32 {
+    dep::add_two(dep::double(x))
+}
+
+// Only translated if `dep::Meter::in_range` was imported, then found vacuous without SuSLik
+#[requires(m.in_range() && m.reading > 5000 && m.reading < 10)]
+fn never(m: &dep::Meter) {}
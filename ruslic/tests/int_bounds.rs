@@ -0,0 +1,61 @@
+use ruslic::suslik::Expr;
+
+fn bounds(bits: u64, signed: bool) -> (String, String) {
+    let (min, max) = Expr::int_range(bits, signed);
+    (min.to_string(), max.to_string())
+}
+
+/// The value of a literal as written for SuSLik, checking that each part fits in a Scala `Int`.
+fn eval(lit: &str) -> u128 {
+    match lit.strip_prefix("((").and_then(|lit| lit.strip_suffix(')')) {
+        Some(lit) => {
+            let (high, low) = lit.rsplit_once(" * 65536) + ").unwrap();
+            let low = eval(low);
+            assert!(low < 65536, "{lit}");
+            (eval(high) << 16) + low
+        }
+        None => {
+            let value: u128 = lit.parse().unwrap();
+            assert!(value <= i32::MAX as u128, "{lit} doesn't fit in an Int");
+            value
+        }
+    }
+}
+
+#[test]
+fn signed_bounds() {
+    for (bits, min, max) in [
+        (8, i8::MIN as i128, i8::MAX as i128),
+        (16, i16::MIN as i128, i16::MAX as i128),
+        (32, i32::MIN as i128, i32::MAX as i128),
+        (64, i64::MIN as i128, i64::MAX as i128),
+        (128, i128::MIN, i128::MAX),
+    ] {
+        assert_eq!(Expr::int_bounds(bits, true), (min, max as u128), "i{bits}");
+        let (min_lit, max_lit) = bounds(bits, true);
+        let neg = min_lit.strip_prefix("(- ").and_then(|lit| lit.strip_suffix(')'));
+        assert_eq!(eval(neg.unwrap()), min.unsigned_abs(), "i{bits}");
+        assert_eq!(eval(&max_lit), max as u128, "i{bits}");
+    }
+    assert_eq!(bounds(8, true), ("(- 128)".into(), "127".into()));
+    assert_eq!(bounds(32, true).1, "2147483647");
+    assert_eq!(bounds(32, true).0, "(- ((32768 * 65536) + 0))");
+}
+
+#[test]
+fn unsigned_bounds() {
+    for (bits, max) in [
+        (8, u8::MAX as u128),
+        (16, u16::MAX as u128),
+        (32, u32::MAX as u128),
+        (64, u64::MAX as u128),
+        (128, u128::MAX),
+    ] {
+        assert_eq!(Expr::int_bounds(bits, false), (0, max), "u{bits}");
+        let (min_lit, max_lit) = bounds(bits, false);
+        assert_eq!(min_lit, "0");
+        assert_eq!(eval(&max_lit), max, "u{bits}");
+    }
+    assert_eq!(bounds(16, false).1, "65535");
+    assert_eq!(bounds(32, false).1, "((65535 * 65536) + 65535)");
+}
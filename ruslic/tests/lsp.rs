@@ -0,0 +1,96 @@
+use std::{io::Cursor, path::PathBuf};
+
+use ruslic::lsp_helpers::{position, read_message, spec_fn_at, uri_to_path};
+use serde_json::json;
+
+fn frame(body: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{body}", body.len())
+}
+
+#[test]
+fn read_framed_messages() {
+    let first = r#"{"id":1,"method":"initialize"}"#;
+    // Other headers are ignored, the length is in bytes
+    let second = r#"{"method":"é"}"#;
+    let input = format!(
+        "{}Content-Type: application/vscode-jsonrpc; charset=utf-8\r\n{}",
+        frame(first),
+        frame(second)
+    );
+    let mut input = Cursor::new(input);
+    assert_eq!(
+        read_message(&mut input),
+        Some(json!({ "id": 1, "method": "initialize" }))
+    );
+    assert_eq!(read_message(&mut input), Some(json!({ "method": "é" })));
+    assert_eq!(read_message(&mut input), None);
+}
+
+#[test]
+fn read_malformed_messages() {
+    // No length
+    assert_eq!(read_message(&mut Cursor::new("\r\n{}")), None);
+    // Cut short
+    assert_eq!(
+        read_message(&mut Cursor::new("Content-Length: 10\r\n\r\n{}")),
+        None
+    );
+    assert_eq!(
+        read_message(&mut Cursor::new("Content-Length: 2\r\n")),
+        None
+    );
+}
+
+#[test]
+fn file_uris() {
+    assert_eq!(
+        uri_to_path("file:///home/me/my%20crate/src/lib.rs"),
+        Some(PathBuf::from("/home/me/my crate/src/lib.rs"))
+    );
+    assert_eq!(
+        uri_to_path("file:///tmp/%C3%A9.rs"),
+        Some(PathBuf::from("/tmp/é.rs"))
+    );
+    assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+    assert_eq!(uri_to_path("file:///tmp/%zz.rs"), None);
+    assert_eq!(uri_to_path("file:///tmp/%2"), None);
+}
+
+#[test]
+fn utf16_positions() {
+    let text = "fn a() {}\nlet s = \"😀é\"; x";
+    let offset = text.find('x').unwrap();
+    assert_eq!(
+        position(text, offset),
+        json!({ "line": 1, "character": 15 })
+    );
+    assert_eq!(position(text, 0), json!({ "line": 0, "character": 0 }));
+    assert_eq!(position(text, 10), json!({ "line": 1, "character": 0 }));
+}
+
+const SRC: &str = r#"
+#[requires(x < 10)]
+#[ensures(result == x + 1)]
+pub fn inc(x: u32) -> u32 {
+    todo!()
+}
+
+fn zero() -> u32 {
+    ruslik!()
+}
+
+/// Not synthesized
+fn plain() {}
+"#;
+
+#[test]
+fn fns_with_specs() {
+    let at = |line| spec_fn_at(SRC, line).map(|f| (f.name, f.line));
+    assert_eq!(at(3), Some(("inc", 3)));
+    // Inside the body
+    assert_eq!(at(4), Some(("inc", 3)));
+    assert_eq!(at(7), Some(("zero", 7)));
+    assert_eq!(at(12), None);
+    // Above every fn
+    assert_eq!(at(0), None);
+}
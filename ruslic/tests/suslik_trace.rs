@@ -0,0 +1,49 @@
+use std::path::Path;
+
+use ruslic::suslik_trace::Explanation;
+
+// These traces are laid out like the output of SuSLik's `-j` (one entry per node or status,
+// separated by blank lines), with the assertions of `pure_spatial.json` as lists and those of
+// `pp.json` pretty-printed. They are written by hand, not recorded from a SuSLik run.
+fn explain(trace: &str) -> Option<Explanation> {
+    let names = [("fx", "x"), ("fy", "y"), ("fv", "v"), ("fresult", "result")]
+        .into_iter()
+        .map(|(field, name)| (field.to_string(), name.to_string()))
+        .collect();
+    Explanation::from_trace(&Path::new("tests/traces").join(trace), &names)
+}
+
+#[test]
+fn pure_spatial_trace() {
+    // Cut short by a timeout in the middle of the last node
+    let explanation = explain("pure_spatial.json").unwrap();
+    assert_eq!(explanation.depth, 2);
+    assert_eq!(explanation.pre, ["`y` fy1: Pu32(snap_fy)"]);
+    assert_eq!(explanation.post, ["`result` fresult: Pu32(snap_fresult)"]);
+    assert_eq!(explanation.obligations, ["(snap_fresult) >= (snap_fx)"]);
+}
+
+#[test]
+fn pp_trace() {
+    let explanation = explain("pp.json").unwrap();
+    assert_eq!(explanation.depth, 1);
+    assert_eq!(
+        explanation.pre,
+        [
+            "`v` fv: P4_Vec_(snap_fv, len_fv)",
+            "`v` fv1: P4_Vec_(snap_fv1, len_fv1)"
+        ]
+    );
+    assert!(explanation.post.is_empty());
+    assert_eq!(
+        explanation.obligations,
+        ["(len_fresult == len_fv)", "((len_fv > 0) && (snap_fresult == snap_fv))"]
+    );
+    assert!(explanation.to_string().contains("need: emp"));
+}
+
+#[test]
+fn unknown_trace() {
+    assert!(explain("unknown.json").is_none());
+    assert!(explain("missing.json").is_none());
+}
@@ -0,0 +1,25 @@
+use std::cell::{Cell, RefCell};
+
+use russol_contracts::*;
+
+#[ensures((^c).get() == x)]
+fn set(c: &Cell<u8>, x: u8) {
+    c.set(x)
+}
+
+#[ensures(result == *r.contents())]
+#[ensures(*(^r).contents() == 0)]
+fn take(r: &RefCell<u16>) -> u16 {
+    r.replace(0)
+}
+
+struct Counter {
+    hits: Cell<u32>,
+    id: u16,
+}
+
+// A `&Counter` can be mutated through its `Cell`, so it's encoded like a `&mut Counter`
+#[ensures((^c).hits.get() == x && (^c).id == c.id)]
+fn reset(c: &Counter, x: u32) {
+    c.hits.set(x)
+}
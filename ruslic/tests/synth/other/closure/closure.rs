@@ -0,0 +1,47 @@
+#![feature(stmt_expr_attributes, proc_macro_hygiene)]
+
+use russol_contracts::*;
+
+// The kind of each closure below is the one these bounds expect, the holes use nothing
+fn call_once<T>(f: impl FnOnce() -> T) -> T {
+    f()
+}
+fn call_mut<T>(mut f: impl FnMut() -> T) -> T {
+    f()
+}
+
+// Only read, so captured by reference: `let v = &v;`
+fn by_ref(v: Vec<u8>) -> usize {
+    let f = #[synth]
+    #[ensures(result == v.len())]
+    || -> usize { ruslik!() };
+    f()
+}
+
+// Moved into a closure which is only called once, so captured by value without a rebinding
+fn by_value_once(b: Box<u16>) -> Box<u16> {
+    call_once(
+        #[synth]
+        #[ensures(*result == *b)]
+        move || -> Box<u16> { ruslik!() },
+    )
+}
+
+// Moved into a closure which can be called again, so mutably borrowed: `let v = &mut v;`
+fn by_value_mut(v: Vec<u8>) -> usize {
+    call_mut(
+        #[synth]
+        #[ensures(result == v.len())]
+        move || -> usize { ruslik!() },
+    )
+}
+
+// A mutable reference moved into such a closure is reborrowed: `let x = &mut *x;`
+fn reborrow(x: &mut u16) {
+    call_mut(
+        #[synth]
+        #[requires(*x < 100)]
+        #[ensures(^x == *x + 1)]
+        move || { ruslik!() },
+    )
+}
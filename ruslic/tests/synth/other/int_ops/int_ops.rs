@@ -0,0 +1,50 @@
+use russol_contracts::*;
+
+// A cast which may not fit wraps around
+#[requires(x as u8 == 0)]
+#[ensures(result == x)]
+fn low_byte_zero(x: u16) -> u16 {
+    x
+}
+
+// Signed division truncates towards zero
+#[requires(x / 2 == -3 && y % 3 == -1)]
+#[ensures(result == x)]
+fn signed_div(x: i8, y: i8) -> i8 {
+    x
+}
+
+// Shifts by a literal and `!`
+#[requires(x >> 4 == 0)]
+#[ensures(result == !x)]
+fn invert(x: u8) -> u8 {
+    !x
+}
+
+// Wrapping arithmetic
+#[ensures(result == x.wrapping_add(1))]
+fn wrapping_inc(x: u8) -> u8 {
+    x.wrapping_add(1)
+}
+
+// Wide types are exact too: `-1i32 as u32` is `u32::MAX`
+#[requires(x < 0)]
+#[ensures(result as i32 == x && result > 2147483647)]
+fn reinterpret(x: i32) -> u32 {
+    x as u32
+}
+
+#[ensures(result == x as u32)]
+fn truncate(x: u64) -> u32 {
+    x as u32
+}
+
+#[ensures(result == x.wrapping_sub(y))]
+fn wrapping_diff(x: u64, y: u64) -> u64 {
+    x.wrapping_sub(y)
+}
+
+#[ensures(result == !x)]
+fn invert_wide(x: usize) -> usize {
+    !x
+}
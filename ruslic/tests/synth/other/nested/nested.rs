@@ -0,0 +1,17 @@
+#![feature(stmt_expr_attributes, proc_macro_hygiene)]
+
+use russol_contracts::*;
+
+// The nested fn and closure are replaced along with the body of `outer`, so they aren't
+// synthesized on their own
+#[ensures(result == x)]
+fn outer(x: u16) -> u16 {
+    #[ensures(result == y)]
+    fn id(y: u16) -> u16 {
+        y
+    }
+    let f = #[ensures(result == x)]
+    || -> u16 { x };
+    id(f())
+}
+
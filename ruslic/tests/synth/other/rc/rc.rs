@@ -0,0 +1,21 @@
+use std::{rc::Rc, sync::Arc};
+
+use russol_contracts::*;
+
+// An `Rc` can only be shared by cloning it
+#[ensures(*result.0 == x && *result.1 == x)]
+fn share(x: u8) -> (Rc<u8>, Rc<u8>) {
+    let a = Rc::new(x);
+    let b = Rc::clone(&a);
+    (a, b)
+}
+
+#[ensures(result == **r)]
+fn read(r: &Rc<u16>) -> u16 {
+    *Rc::as_ref(r)
+}
+
+#[ensures(*result == **a)]
+fn share_arc(a: &Arc<i32>) -> Arc<i32> {
+    Arc::clone(a)
+}
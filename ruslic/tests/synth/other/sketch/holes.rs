@@ -0,0 +1,58 @@
+use russol_contracts::*;
+
+enum Shape {
+    Circle(u16),
+    Rect(u16, u16),
+}
+
+// `c` is copied into the context of the hole, with what it is equal to
+#[requires(a < 1000 && b < 1000)]
+#[ensures(result == a + b + 1)]
+fn add_one(a: u32, b: u32) -> u32 {
+    let c = a + 1;
+    ruslik!()
+}
+
+// The scrutinee is replaced by the bindings of each arm
+#[requires(match s { Shape::Circle(r) => r < 100, Shape::Rect(w, h) => w < 100 && h < 100 })]
+#[ensures(match s { Shape::Circle(r) => result == 3 * r * r, Shape::Rect(w, h) => result == w * h })]
+fn area(s: Shape) -> u16 {
+    match s {
+        Shape::Circle(r) => ruslik!(),
+        Shape::Rect(w, h) => ruslik!(),
+    }
+}
+
+// The condition holds in the context of the hole
+#[ensures(result >= x && result >= y)]
+fn max(x: u32, y: u32) -> u32 {
+    if x < y {
+        ruslik!()
+    } else {
+        x
+    }
+}
+
+// The context does not track assignments, so this hole is unsupported (it would otherwise
+// be synthesized with `*x` still being the old value) and the whole fn is synthesized instead
+#[ensures(^x == *x + 1)]
+fn increment(x: &mut u32) {
+    *x = 1;
+    ruslik!()
+}
+
+// Nor moves into a `let`, which would leave `b` in the context as well
+#[ensures(*result === *b)]
+fn rebox(b: Box<u32>) -> Box<u32> {
+    let c = b;
+    ruslik!()
+}
+
+// Nor shadowing, since the hole refers to the new `x` by name while the facts and the
+// postcondition refer to the old one (`let x = x + 1;` would give the fact `x == x + 1`)
+#[requires(x < 1000)]
+#[ensures(result == x + 2)]
+fn add_two(x: u32) -> u32 {
+    let x = x + 1;
+    ruslik!()
+}
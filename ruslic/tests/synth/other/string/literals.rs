@@ -0,0 +1,20 @@
+use russol_contracts::*;
+
+#[ensures(result == "")]
+fn empty() -> String {
+    String::new()
+}
+
+// Literals of any length stand for a fixed snapshot
+#[requires(a == "a first literal, longer than an integer")]
+#[requires(b == "a second literal, longer than an integer")]
+#[ensures(result == "a second literal, longer than an integer")]
+fn pick_second(a: String, b: String) -> String {
+    b
+}
+
+#[requires(s == "a third literal, longer than an integer")]
+#[ensures(result == 39)]
+fn literal_len(s: &String) -> usize {
+    s.len()
+}
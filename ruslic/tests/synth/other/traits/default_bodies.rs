@@ -0,0 +1,14 @@
+use russol_contracts::*;
+
+trait Scale {
+    #[requires(x < 128)]
+    #[ensures(result == 2 * x)]
+    fn double(&self, x: u8) -> u8;
+
+    // Glue code synthesized from the required method
+    #[requires(x < 64)]
+    #[ensures(result == 4 * x)]
+    fn quadruple(&self, x: u8) -> u8 {
+        ruslik!()
+    }
+}
@@ -0,0 +1,22 @@
+use russol_contracts::*;
+
+trait Shape {
+    #[ensures(result > 0)]
+    fn sides(&self) -> u8;
+}
+
+struct Named {
+    shape: Box<dyn Shape>,
+    id: u16,
+}
+
+// A `dyn Shape` can be moved around, but only inspected through its methods
+#[ensures(result.id == id)]
+fn name(shape: Box<dyn Shape>, id: u16) -> Named {
+    Named { shape, id }
+}
+
+#[ensures(result > 0)]
+fn sides_of(s: &dyn Shape) -> u8 {
+    s.sides()
+}
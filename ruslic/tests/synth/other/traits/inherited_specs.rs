@@ -0,0 +1,31 @@
+use russol_contracts::*;
+
+trait Step {
+    #[requires(x < 255)]
+    #[ensures(result == x + 1)]
+    fn step(&self, x: u8) -> u8;
+}
+
+struct Inc;
+
+// Synthesized against the specs of `Step::step`
+impl Step for Inc {
+    fn step(&self, x: u8) -> u8 {
+        x + 1
+    }
+}
+
+struct Hole;
+
+impl Step for Hole {
+    fn step(&self, x: u8) -> u8 {
+        ruslik!()
+    }
+}
+
+// `step` is a component with the trait's specs
+#[requires(x < 254)]
+#[ensures(result == x + 2)]
+fn twice<S: Step>(s: &S, x: u8) -> u8 {
+    s.step(s.step(x))
+}
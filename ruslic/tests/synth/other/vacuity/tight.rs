@@ -0,0 +1,27 @@
+use russol_contracts::*;
+
+// Tight specs which are still satisfiable, so they mustn't be reported as vacuous
+
+#[requires(x > 5 && x < 7)]
+#[ensures(result == 6)]
+fn only_six(x: u8) -> u8 {
+    x
+}
+
+#[requires(x >= 255)]
+#[ensures(result == x)]
+fn max_u8(x: u8) -> u8 {
+    x
+}
+
+#[requires(x < 255)]
+#[ensures(result == x + 1)]
+fn succ(x: u8) -> u8 {
+    x + 1
+}
+
+#[requires(x > 70000 && y < -2147483649)]
+#[ensures(result == x)]
+fn wide(x: u32, y: i64) -> u32 {
+    x
+}
@@ -0,0 +1,31 @@
+use russol_contracts::*;
+
+#[ensures(result.len() == 1)]
+#[ensures(result.elems() == seq![&x])]
+fn singleton(x: u8) -> Vec<u8> {
+    let mut v = Vec::new();
+    v.push(x);
+    v
+}
+
+#[ensures((^v).len() == v.len() + 2)]
+#[ensures((^v).elems() == v.elems() + seq![&x] + seq![&y])]
+fn push_two(v: &mut Vec<i32>, x: i32, y: i32) {
+    v.push(x);
+    v.push(y)
+}
+
+// The ghosts in the spec of `insert` aren't tied to `i`, so only the `len` is known after it
+#[requires(v.len() > 0)]
+#[ensures((^v).len() == v.len() + 1)]
+fn insert_front(v: &mut Vec<u16>, x: u16) {
+    v.insert(0, x)
+}
+
+#[requires(v.len() == 1)]
+#[ensures((^v).elems() == v.elems() + seq![&v[0]])]
+fn dup_single(v: &mut Vec<u8>) {
+    let x = v.pop().unwrap();
+    v.push(x);
+    v.push(x)
+}
@@ -214,6 +214,13 @@ impl<'a, T: Iterator<Item = &'a SynthesisResult> + Clone> Display for KrateResul
         for (r, (c, _non_main)) in self.reason_count() {
             write!(f, "{r:?} {c}, ")?;
         }
+        let top_tys = Unsupported::top_tys(self.unsupported().map(|(_, u)| u), 20);
+        if !top_tys.is_empty() {
+            write!(f, "| Top unsupported types: ")?;
+            for (ty, _, c) in top_tys {
+                write!(f, "`{ty}` {c}, ")?;
+            }
+        }
         writeln!(f)
     }
 }
@@ -326,6 +333,7 @@ fn run_on_crate(dirname: &str, offline: bool) -> Vec<SynthesisResult> {
         .env("RUSLIC_TIMEOUT", timeout)
         .env("RUSLIC_FAIL_ON_UNSYNTH", "false")
         .env("RUSLIC_SUBST_RESULT", "true")
+        .env("RUSLIC_SYNTH_BODIES", "true")
         .env("RUSLIC_PRINT_SLN_ABOVE", "1")
         .env("RUSLIC_SUMMARISE_JSON", "true")
         .env("RUSLIC_OUTPUT_TRACE", "true")
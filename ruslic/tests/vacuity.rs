@@ -0,0 +1,28 @@
+use ruslic::{
+    config::RuslicConfig,
+    suslik::{SynthesisResultKind, Vacuity},
+};
+
+#[test]
+fn reports_vacuous_specs() {
+    let config = RuslicConfig {
+        synth_bodies: true,
+        ..Default::default()
+    };
+    let args = ["/name/of/binary", "tests/vacuity/vacuous.rs"];
+    let res = ruslic::run_on_file(args.iter().map(|arg| arg.to_string()).collect(), &config, false)
+        .unwrap();
+    let vacuity = |name: &str| match res[name].kind {
+        SynthesisResultKind::Vacuous(vacuity) => Some(vacuity),
+        _ => None,
+    };
+    assert_eq!(vacuity("contradiction"), Some(Vacuity::Pre));
+    assert_eq!(vacuity("out_of_range"), Some(Vacuity::Pre));
+    assert_eq!(vacuity("no_result"), Some(Vacuity::Post));
+    assert_eq!(vacuity("succ"), None);
+    assert_eq!(vacuity("above_u16"), None);
+    assert_eq!(vacuity("above_u32"), None);
+    // Beyond the exact range of the type
+    assert_eq!(vacuity("above_u64"), Some(Vacuity::Pre));
+    assert_eq!(vacuity("below_i64_min"), Some(Vacuity::Post));
+}
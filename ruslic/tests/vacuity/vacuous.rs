@@ -0,0 +1,45 @@
+use russol_contracts::*;
+
+#[requires(x > 5 && x < 3)]
+fn contradiction(x: u8) -> u8 {
+    x
+}
+
+// Contradicts the range of a `u8`
+#[requires(x > 255)]
+fn out_of_range(x: u8) -> u8 {
+    x
+}
+
+#[ensures(result > x && result < x)]
+fn no_result(x: i32) -> i32 {
+    x
+}
+
+#[requires(x < 255)]
+#[ensures(result == x + 1)]
+fn succ(x: u8) -> u8 {
+    x + 1
+}
+
+// Satisfiable only above the range of a `u16` or `i32`, which wide types cover
+#[requires(x > 70000)]
+fn above_u16(x: u32) -> u32 {
+    x
+}
+
+#[requires(x > 4294967296 && y < -2147483649)]
+fn above_u32(x: u64, y: i64) -> u64 {
+    x
+}
+
+#[requires(x > 18446744073709551615)]
+fn above_u64(x: u64) -> u64 {
+    x
+}
+
+#[requires(x < -9223372036854775807)]
+#[ensures(result < x)]
+fn below_i64_min(x: i64) -> i64 {
+    x
+}
@@ -1,5 +1,7 @@
 #![no_std]
 
+extern crate alloc;
+
 /// A macro for writing a precondition on a function.
 pub use russol_macros::requires;
 pub fn requires<R, T: Fn(R) -> bool>(_closure: T) {}
@@ -108,3 +110,63 @@ impl<T> Ord for Set<T> {
         panic!()
     }
 }
+
+/// The element sequence of a `Vec`, see [`Elems`]
+#[non_exhaustive]
+#[derive(Copy, Clone)]
+pub struct Seq<T>(core::marker::PhantomData<T>);
+impl<T> Seq<T> {
+    pub fn new(_: &[&T]) -> Self {
+        panic!()
+    }
+    /// The first `n` elements
+    pub fn take(self, _n: usize) -> Self {
+        panic!()
+    }
+    /// All but the first `n` elements
+    pub fn skip(self, _n: usize) -> Self {
+        panic!()
+    }
+}
+
+#[macro_export]
+macro_rules! seq {
+    ($($val:expr),*) => { $crate::Seq::new(&[$($val,)*]) };
+}
+impl<T> core::ops::Add for Seq<T> {
+    type Output = Self;
+    fn add(self, _: Self) -> Self {
+        panic!()
+    }
+}
+impl<T> Eq for Seq<T> {}
+impl<T> PartialEq for Seq<T> {
+    fn eq(&self, _: &Self) -> bool {
+        panic!()
+    }
+}
+
+/// Gives the elements of a `Vec` in specifications, e.g. `(^v).elems() == v.elems() + seq![&x]`
+pub trait Elems {
+    type Elem;
+    fn elems(&self) -> Seq<Self::Elem> {
+        panic!("Cannot take elems in executable code!")
+    }
+}
+impl<T> Elems for alloc::vec::Vec<T> {
+    type Elem = T;
+}
+
+/// Gives the value inside a `Cell` or `RefCell` in specifications, e.g. `*(^c).contents() == 1`
+pub trait Contents {
+    type Target: ?Sized;
+    fn contents(&self) -> &Self::Target {
+        panic!("Cannot take contents in executable code!")
+    }
+}
+impl<T: ?Sized> Contents for core::cell::Cell<T> {
+    type Target = T;
+}
+impl<T: ?Sized> Contents for core::cell::RefCell<T> {
+    type Target = T;
+}
@@ -5,11 +5,11 @@
 
 extern crate alloc;
 
-use alloc::string::ToString;
+use alloc::{string::ToString, vec::Vec};
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::ToTokens;
-use syn::parse_quote;
+use syn::{parse_quote, spanned::Spanned};
 
 mod rewriter;
 
@@ -60,16 +60,26 @@ pub fn helper(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Also allowed on a closure, to synthesize it without the fn it is in.
 #[proc_macro_attribute]
 pub fn synth(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
-    match syn::parse2::<syn::ItemFn>(tokens.into()) {
+    let tokens: TokenStream2 = tokens.into();
+    match syn::parse2::<syn::ItemFn>(tokens.clone()) {
         Ok(mut item_fn) => {
             item_fn
                 .attrs
                 .push(parse_quote! { #[rustfmt::ruslik_synth] });
             item_fn.into_token_stream()
         }
-        Err(e) => e.to_compile_error(),
+        Err(e) => match parse_closure(tokens) {
+            Some(mut closure) => {
+                closure
+                    .attrs
+                    .push(parse_quote! { #[rustfmt::ruslik_synth] });
+                closure.into_token_stream()
+            }
+            None => e.to_compile_error(),
+        },
     }
     .into()
 }
@@ -89,13 +99,21 @@ pub fn params(attr: TokenStream, tokens: TokenStream) -> TokenStream {
     .into()
 }
 
+/// `#[extern_spec]`, or `#[extern_spec(path::to::fn)]` to have solutions call `path::to::fn`
+/// rather than the annotated wrapper.
 #[proc_macro_attribute]
-pub fn extern_spec(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
+pub fn extern_spec(attr: TokenStream, tokens: TokenStream) -> TokenStream {
     match syn::parse2::<syn::ItemFn>(tokens.into()) {
         Ok(mut item_fn) => {
             item_fn
                 .attrs
                 .push(parse_quote! { #[rustfmt::ruslik_extern_spec] });
+            if !attr.is_empty() {
+                let path = attr.to_string().replace(' ', "");
+                item_fn
+                    .attrs
+                    .push(parse_quote! { #[rustfmt::ruslik_extern_path = #path] });
+            }
             item_fn.into_token_stream()
         }
         Err(e) => e.to_compile_error(),
@@ -114,15 +132,131 @@ fn parse_fn_specs(
     attr: TokenStream2,
     attr_kind: SpecKind,
 ) -> Result<TokenStream2, TokenStream2> {
-    let mut item_fn: syn::ItemFn = syn::parse2(fun).map_err(|e| e.to_compile_error())?;
-    let orig_len = item_fn.block.stmts.len();
-    item_fn.block.stmts.insert(
-        0,
-        rewriter::parse_attr(attr, attr_kind, &item_fn.sig.output)?,
-    );
-
-    item_fn.attrs = item_fn
+    let mut item_fn: syn::ItemFn = match syn::parse2(fun.clone()) {
+        Ok(item_fn) => item_fn,
+        Err(e) => match syn::parse2::<syn::TraitItemMethod>(fun.clone()) {
+            Ok(method) if method.default.is_none() => {
+                return parse_trait_method_specs(method, attr, attr_kind)
+            }
+            _ => match parse_closure(fun) {
+                Some(closure) => return parse_closure_specs(closure, attr, attr_kind),
+                None => return Err(e.to_compile_error()),
+            },
+        },
+    };
+    add_fn_specs(&mut item_fn, attr, attr_kind)?;
+    Ok(item_fn.into_token_stream())
+}
+
+/// Parsing as an `Expr` keeps the other attributes on the closure. As an argument of a call,
+/// the closure is passed to the attribute together with the `,` after it, which is dropped.
+fn parse_closure(tokens: TokenStream2) -> Option<syn::ExprClosure> {
+    let parser = |input: syn::parse::ParseStream| {
+        let expr = input.parse()?;
+        input.parse::<Option<syn::Token![,]>>()?;
+        Ok(expr)
+    };
+    match syn::parse::Parser::parse2(parser, tokens) {
+        Ok(syn::Expr::Closure(closure)) => Some(closure),
+        _ => None,
+    }
+}
+
+/// The specs of a closure go at the start of its body like for a fn, the body is made a block
+/// if it isn't one. The closure needs an explicit return type for `result` to be used.
+fn parse_closure_specs(
+    mut closure: syn::ExprClosure,
+    attr: TokenStream2,
+    attr_kind: SpecKind,
+) -> Result<TokenStream2, TokenStream2> {
+    let body = &closure.body;
+    let mut block: syn::Block = match &**body {
+        syn::Expr::Block(syn::ExprBlock {
+            attrs,
+            label: None,
+            block,
+        }) if attrs.is_empty() => block.clone(),
+        _ => syn::parse_quote_spanned! { body.span() => { #body } },
+    };
+    add_specs(
+        &mut closure.attrs,
+        &mut block,
+        &closure.output,
+        attr,
+        attr_kind,
+    )?;
+    closure.body = parse_quote! { #block };
+    Ok(closure.into_token_stream())
+}
+
+/// A trait method declaration has no body to put the specs in, so they go in a hidden sibling
+/// `__russol_spec_<name>` method with the same signature instead. It is excluded from trait
+/// objects, and `ruslic` reads its specs as those of the declaration.
+fn parse_trait_method_specs(
+    mut method: syn::TraitItemMethod,
+    attr: TokenStream2,
+    attr_kind: SpecKind,
+) -> Result<TokenStream2, TokenStream2> {
+    let (spec_attrs, attrs) = method.attrs.into_iter().partition(|attr| {
+        matches!(
+            attr.path.segments[0].ident.to_string().as_str(),
+            "requires" | "ensures"
+        )
+    });
+    method.attrs = attrs;
+    let name = method.sig.ident.to_string();
+    let mut sig = method.sig.clone();
+    sig.ident = syn::Ident::new(&("__russol_spec_".to_string() + &name), sig.ident.span());
+    sig.generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote! { Self: Sized });
+    let mut spec_fn = syn::ItemFn {
+        attrs: spec_attrs,
+        vis: syn::Visibility::Inherited,
+        sig,
+        block: parse_quote! {{ unreachable!() }},
+    };
+    add_fn_specs(&mut spec_fn, attr, attr_kind)?;
+    spec_fn.attrs.push(parse_quote! { #[doc(hidden)] });
+    spec_fn
+        .attrs
+        .push(parse_quote! { #[allow(unused_variables)] });
+    spec_fn
         .attrs
+        .push(parse_quote! { #[rustfmt::ruslik_trait_spec = #name] });
+    let mut tokens = method.into_token_stream();
+    spec_fn.to_tokens(&mut tokens);
+    Ok(tokens)
+}
+
+fn add_fn_specs(
+    item_fn: &mut syn::ItemFn,
+    attr: TokenStream2,
+    attr_kind: SpecKind,
+) -> Result<(), TokenStream2> {
+    add_specs(
+        &mut item_fn.attrs,
+        &mut item_fn.block,
+        &item_fn.sig.output,
+        attr,
+        attr_kind,
+    )
+}
+
+fn add_specs(
+    attrs: &mut Vec<syn::Attribute>,
+    block: &mut syn::Block,
+    output: &syn::ReturnType,
+    attr: TokenStream2,
+    attr_kind: SpecKind,
+) -> Result<(), TokenStream2> {
+    let orig_len = block.stmts.len();
+    block
+        .stmts
+        .insert(0, rewriter::parse_attr(attr, attr_kind, output)?);
+
+    *attrs = core::mem::take(attrs)
         .into_iter()
         .filter_map(|attr| {
             let sk = match attr.path.segments[0].ident.to_string().as_str() {
@@ -131,9 +265,9 @@ fn parse_fn_specs(
                 "ruslik_spec_count" => panic!("The attribute `ruslik_spec_count` is reserved!"),
                 _ => return Some(Ok(attr)),
             };
-            match rewriter::parse_attr(attr.tokens, sk, &item_fn.sig.output) {
+            match rewriter::parse_attr(attr.tokens, sk, output) {
                 Ok(expr) => {
-                    item_fn.block.stmts.insert(0, expr);
+                    block.stmts.insert(0, expr);
                     None
                 }
                 Err(e) => Some(Err(e)),
@@ -141,13 +275,11 @@ fn parse_fn_specs(
         })
         .collect::<Result<_, _>>()?;
 
-    let spec_count = (item_fn.block.stmts.len() - orig_len).to_string();
+    let spec_count = (block.stmts.len() - orig_len).to_string();
     // Either we register `ruslik` as a tool (but this runs into issues when compiling without ruslic)
     // Or we hijack a built-in tool for attributes (`rustfmt` or `clippy`)
-    item_fn
-        .attrs
-        .push(parse_quote! { #[rustfmt::ruslik_spec_count = #spec_count] });
-    Ok(item_fn.into_token_stream())
+    attrs.push(parse_quote! { #[rustfmt::ruslik_spec_count = #spec_count] });
+    Ok(())
 }
 
 #[proc_macro]